@@ -0,0 +1,17 @@
+use crate::{usm, Instruction, Value};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+// FNV-1a over the serialized form of each instruction and data value, so the hash
+// depends only on the program itself and not on how it was stored
+pub fn program_hash(program: &[Instruction], data: &[Value]) -> u64 {
+    program
+        .iter()
+        .map(|inst| usm::serialize(*inst))
+        .chain(data.iter().map(|val| usm::serialize_data(*val)))
+        .flatten()
+        .fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+        })
+}
@@ -1,15 +1,34 @@
+mod hash;
+mod opt;
 mod usm;
 mod utils;
-use crate::usm::{Instruction, InstructionKind, SerializedInst, Value, INST_CHUNCK_SIZE};
+mod watch;
+use crate::usm::{
+    Category, Instruction, InstructionKind, LabelTable, ParseOptions, SerializedInst, SourceInfo,
+    Value,
+};
 use std::{
+    collections::HashMap,
     fs,
-    io::{self, Write},
-    path::Path,
+    io::{self, BufRead, BufWriter, IsTerminal, Read, Write},
+    ops::Range,
+    path::{Component, Path, PathBuf},
+    time::{Instant, SystemTime, UNIX_EPOCH},
 };
 use utils::Array;
+use watch::WatchExpr;
 
 const VM_STACK_CAPACITY: usize = 1024;
-const PROGRAM_INST_CAPACITY: usize = 1024;
+// Deepest nesting of `виклик`
+const RETURN_STACK_CAPACITY: usize = 1024;
+const FRAMES_CAPACITY: usize = 256;
+const LOCALS_CAPACITY: usize = 1024;
+const MEMORY_CAPACITY: usize = 64 * 1024;
+const REGISTERS_COUNT: usize = 8;
+const EMPTY_PROGRAM_NOTICE: &str = "УВАГА: програма не містить інструкцій";
+const COERCION_WARNINGS_PER_ADDR: usize = 1;
+// Longest number `читай` takes, the longer ones are skipped without buffering them
+const READ_TOKEN_CAPACITY: usize = 64;
 
 type VMResult<T> = Result<T, Panic>;
 
@@ -17,139 +36,962 @@ type VMResult<T> = Result<T, Panic>;
 pub enum Panic {
     ReadFileErr(io::Error),
     WriteToFileErr(io::Error),
-    ParseError(String),
+    OutputNotReplaced(io::Error),
+    OutputIsInput(String),
+    EmptyProgram,
+    InvalidEncoding {
+        byte_offset: usize,
+    },
+    ParseError {
+        message: String,
+        line: usize,
+        col: usize,
+    },
+    CorruptedBytecode(String),
     StackOverflow,
     StackUnderflow,
+    CallStackUnderflow,
+    ReturnStackOverflow,
+    ReturnStackUnderflow,
     ValueOverflow,
+    ValueUnderflow,
     DivByZero,
+    // The operand has no real result, like the root of a negative number
+    FloatDomain(Value),
+    InvalidOperandValue(Value),
+    // The text that is not a number
+    InputError(String),
+    InputTooLong,
+    EndOfInput,
+    MemoryOutOfBounds(Value),
+    UnknownSyscall(usize),
+    // The path of a file syscall that the sandbox does not let through
+    PermissionDenied(String),
+    AssertionFailed {
+        inst_ptr: usize,
+    },
+    CapabilityDenied {
+        kind: InstructionKind,
+        inst_ptr: usize,
+    },
+}
+
+impl Panic {
+    // For the errors that do not point into a USM source
+    pub fn parse_error(message: String) -> Self {
+        Panic::ParseError {
+            message,
+            line: 0,
+            col: 0,
+        }
+    }
+}
+
+// Set of instruction categories the program is allowed to use
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Capabilities(u8);
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self(u8::MAX)
+    }
+}
+
+impl Capabilities {
+    fn bit(category: Category) -> u8 {
+        match category {
+            Category::Pure => 0,
+            category => 1 << category as u8,
+        }
+    }
+
+    pub fn deny(self, category: Category) -> Self {
+        Self(self.0 & !Self::bit(category))
+    }
+
+    pub fn allows(&self, category: Category) -> bool {
+        category == Category::Pure || self.0 & Self::bit(category) != 0
+    }
+}
+
+type EnvLookup = Box<dyn Fn(&str) -> Option<String> + Send + Sync>;
+
+// Files the syscalls are allowed to read and write
+#[derive(Debug, Default, PartialEq)]
+enum FsAccess {
+    #[default]
+    Denied,
+    Anywhere,
+    // Canonical path of the directory, nothing outside of it is reachable
+    Under(PathBuf),
+}
+
+// Everything the instructions with external effects talk to,
+// so a run can be pointed at something other than the real process
+struct HostServices {
+    stdout: Box<dyn Write + Send + Sync>,
+    stderr: Box<dyn Write + Send + Sync>,
+    stdin: Box<dyn BufRead + Send + Sync>,
+    // Nanoseconds since the start of the run, read by `тік`
+    clock: Box<dyn FnMut() -> u64 + Send + Sync>,
+    // State of the xorshift64 generator behind `випадк`
+    rng: u64,
+    env: EnvLookup,
+    // The prompts of `читай` are printed only to a terminal
+    interactive: bool,
+    fs: FsAccess,
+}
+
+impl Default for HostServices {
+    fn default() -> Self {
+        let started = Instant::now();
+        let mut host = Self {
+            stdout: Box::new(io::stdout()),
+            stderr: Box::new(io::stderr()),
+            stdin: Box::new(io::BufReader::new(io::stdin())),
+            clock: Box::new(move || started.elapsed().as_nanos() as u64),
+            rng: 0,
+            env: Box::new(|name| std::env::var(name).ok()),
+            interactive: io::stdout().is_terminal(),
+            fs: FsAccess::Denied,
+        };
+        host.seed(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |t| t.as_nanos() as u64),
+        );
+
+        host
+    }
+}
+
+impl HostServices {
+    // Same results for the same program on every run: the output is dropped, the clock
+    // moves by one nanosecond on every reading and there are no environment variables
+    #[cfg(test)]
+    fn deterministic(seed: u64, input: &str) -> Self {
+        let mut now = 0;
+        let mut host = Self {
+            stdout: Box::new(io::sink()),
+            stderr: Box::new(io::sink()),
+            stdin: Box::new(io::Cursor::new(input.to_owned())),
+            clock: Box::new(move || {
+                now += 1;
+                now
+            }),
+            rng: 0,
+            env: Box::new(|_| None),
+            interactive: false,
+            fs: FsAccess::Denied,
+        };
+        host.seed(seed);
+
+        host
+    }
+
+    fn seed(&mut self, seed: u64) {
+        // xorshift never leaves zero, so it gets an arbitrary odd state instead
+        self.rng = if seed == 0 {
+            0x9e37_79b9_7f4a_7c15
+        } else {
+            seed
+        };
+    }
+
+    fn next_random(&mut self) -> u64 {
+        let mut x = self.rng;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.rng = x;
+        x
+    }
+
+    fn env(&self, name: &str) -> Option<String> {
+        (self.env)(name)
+    }
+
+    // Resolves the path of a file syscall, a relative path starts at the root of the sandbox.
+    // The links and `..` are resolved before the check, the file itself may not exist yet.
+    fn sandboxed(&self, path: &str) -> VMResult<PathBuf> {
+        let denied = || Panic::PermissionDenied(path.to_string());
+        let root = match &self.fs {
+            FsAccess::Denied => return Err(denied()),
+            FsAccess::Anywhere => return Ok(PathBuf::from(path)),
+            FsAccess::Under(root) => root,
+        };
+        let full = root.join(path);
+        let resolved = match fs::canonicalize(&full) {
+            Ok(resolved) => resolved,
+            Err(_) => match (full.parent(), full.components().next_back()) {
+                (Some(dir), Some(Component::Normal(name))) => {
+                    fs::canonicalize(dir).map_err(|_| denied())?.join(name)
+                }
+                _ => return Err(denied()),
+            },
+        };
+        if !resolved.starts_with(root) {
+            return Err(denied());
+        }
+
+        Ok(resolved)
+    }
+
+    // Skips the leading whitespace. A token longer than `READ_TOKEN_CAPACITY`
+    // is read to its end, so the next reading starts after it.
+    fn read_token(&mut self) -> VMResult<String> {
+        let mut token = Vec::new();
+        let mut len = 0;
+        while let Some(&byte) = self.stdin.fill_buf().map_err(Panic::ReadFileErr)?.first() {
+            self.stdin.consume(1);
+            if !byte.is_ascii_whitespace() {
+                if len < READ_TOKEN_CAPACITY {
+                    token.push(byte);
+                }
+                len += 1;
+            } else if len > 0 {
+                break;
+            }
+        }
+
+        match len {
+            0 => Err(Panic::EndOfInput),
+            n if n > READ_TOKEN_CAPACITY => Err(Panic::InputTooLong),
+            _ => Ok(String::from_utf8_lossy(&token).into_owned()),
+        }
+    }
+
+    // `None` at the end of the input
+    fn read_char(&mut self) -> io::Result<Option<char>> {
+        let mut bytes = [0; 4];
+        if self.stdin.read(&mut bytes[..1])? == 0 {
+            return Ok(None);
+        }
+        let len = match bytes[0].leading_ones() {
+            0 => 1,
+            n @ 2..=4 => n as usize,
+            _ => 0,
+        };
+        if len > 1 {
+            self.stdin.read_exact(&mut bytes[1..len])?;
+        }
+
+        std::str::from_utf8(&bytes[..len])
+            .ok()
+            .and_then(|s| s.chars().next())
+            .map(Some)
+            .ok_or(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "ввід не є UTF-8",
+            ))
+    }
+}
+
+impl std::fmt::Debug for HostServices {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("HostServices").finish_non_exhaustive()
+    }
 }
 
 #[derive(Debug, Default)]
 struct VM {
     stack: Array<Value, VM_STACK_CAPACITY>,
-    program: Array<Instruction, PROGRAM_INST_CAPACITY>,
+    program: Vec<Instruction>,
+    labels: LabelTable,
     inst_ptr: usize,
+    inst_count: usize,
+    stack_peak: usize,
+    frames_peak: usize,
+    calls_peak: usize,
+    // Highest address of the memory that was read or written, `None` until the first access
+    memory_peak: Option<usize>,
+    capabilities: Capabilities,
+    // Local values of all open frames, `frames` keeps where each of them starts
+    locals: Array<Value, LOCALS_CAPACITY>,
+    frames: Array<usize, FRAMES_CAPACITY>,
+    // Return addresses of `виклик`, separate from the values
+    return_stack: Array<usize, RETURN_STACK_CAPACITY>,
+    // Deepest allowed nesting of `виклик`, `None` allows the whole return stack
+    call_depth: Option<usize>,
+    host: HostServices,
+    // Number of printed coercion warnings for each address, `None` when they are off
+    coercion_warnings: Option<HashMap<usize, usize>>,
+    // Cells up to the highest written address, the rest of the `MEMORY_CAPACITY` reads as 0
+    memory: Vec<Value>,
+    // Values of the `дані` directives, copied into the memory before the run
+    data: Vec<Value>,
+    // Source info of every instruction and the text it points into, empty for the bytecode
+    source: Vec<SourceInfo>,
+    source_text: String,
+    // `Null` until written, read as 0
+    registers: [Value; REGISTERS_COUNT],
 }
 
+// VM instances are moved between threads, so any boxed writer or hook
+// added to it has to be `Send` (and `Sync` for shared hooks) as well
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<VM>();
+};
+
 impl VM {
+    fn with_capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    fn memory_addr(&self, addr: Value) -> VMResult<usize> {
+        let a = addr.into_float()?;
+        if a < 0.0 || a.fract() != 0.0 || a >= MEMORY_CAPACITY as f64 {
+            return Err(Panic::MemoryOutOfBounds(addr));
+        }
+        Ok(a as usize)
+    }
+
+    // `end` is one past the last accessed cell, an empty range touches nothing
+    fn touch_memory(&mut self, end: usize) {
+        if let Some(last) = end.checked_sub(1) {
+            self.memory_peak = self.memory_peak.max(Some(last));
+        }
+    }
+
+    // Addresses of the `len` cells from `addr`, all of them have to be in the memory
+    fn memory_range(&mut self, addr: Value, len: usize) -> VMResult<Range<usize>> {
+        let start = self.memory_addr(addr)?;
+        let end = start
+            .checked_add(len)
+            .filter(|e| *e <= MEMORY_CAPACITY)
+            .ok_or(Panic::MemoryOutOfBounds(addr))?;
+        self.touch_memory(end);
+
+        Ok(start..end)
+    }
+
+    // Pops the `адр n` of a string in the memory, the whole string is checked before it is used
+    fn pop_string(&mut self) -> VMResult<String> {
+        let len = self.stack_pop()?.into_uint()?;
+        let addr = self.stack_pop()?;
+        self.memory_range(addr, len)?
+            .map(|a| as_char(self.memory.get(a).copied().unwrap_or(Value::Int(0))))
+            .collect()
+    }
+
+    // Pops the `адр n` of the cells that hold bytes
+    fn pop_bytes(&mut self) -> VMResult<Vec<u8>> {
+        let len = self.stack_pop()?.into_uint()?;
+        let addr = self.stack_pop()?;
+        self.memory_range(addr, len)?
+            .map(|a| {
+                let cell = self.memory.get(a).copied().unwrap_or(Value::Int(0));
+                match cell {
+                    Value::Int(b) => u8::try_from(b).ok(),
+                    Value::Uint(b) => u8::try_from(b).ok(),
+                    _ => None,
+                }
+                .ok_or(Panic::InvalidOperandValue(cell))
+            })
+            .collect()
+    }
+
+    fn register(&self, idx: Value) -> VMResult<Value> {
+        match idx.into_uint().ok().and_then(|i| self.registers.get(i)) {
+            Some(Value::Null) => Ok(Value::Uint(0)),
+            Some(v) => Ok(*v),
+            None => Err(Panic::InvalidOperandValue(idx)),
+        }
+    }
+
+    // Every syscall takes its arguments from the stack, the first one is the deepest:
+    //     1 - write ( fd адр n -- k ), writes n cells of the memory as bytes to
+    //         the fd 1 (stdout) or 2 (stderr) and pushes the number of written bytes
+    //     2 - `файл_читати` ( шлях n адр -- k ), reads the file with the path string
+    //         into the memory from адр, a byte per cell, and pushes the number of read bytes
+    //     3 - `файл_писати` ( шлях n адр k -- k ), writes k cells of the memory as bytes
+    //         into the file with the path string and pushes the number of written bytes
+    // The files are reachable only with `--allow-fs`, otherwise they are `PermissionDenied`.
+    fn syscall(&mut self, number: Value) -> VMResult<()> {
+        match number.into_uint()? {
+            1 => {
+                let bytes = self.pop_bytes()?;
+                let fd = self.stack_pop()?;
+                let out = match fd.into_uint() {
+                    Ok(1) => &mut self.host.stdout,
+                    Ok(2) => &mut self.host.stderr,
+                    _ => return Err(Panic::InvalidOperandValue(fd)),
+                };
+                out.write_all(&bytes).map_err(Panic::WriteToFileErr)?;
+                self.stack_push(Value::Uint(bytes.len()))
+            }
+            2 => {
+                let addr = self.stack_pop()?;
+                let path = self.pop_string()?;
+                let path = self.host.sandboxed(&path)?;
+                let bytes = fs::read(path).map_err(Panic::ReadFileErr)?;
+                let cells = self.memory_range(addr, bytes.len())?;
+                if cells.end > self.memory.len() {
+                    self.memory.resize(cells.end, Value::Int(0));
+                }
+                for (cell, byte) in self.memory[cells].iter_mut().zip(&bytes) {
+                    *cell = Value::Uint(*byte as usize);
+                }
+                self.stack_push(Value::Uint(bytes.len()))
+            }
+            3 => {
+                let bytes = self.pop_bytes()?;
+                let path = self.pop_string()?;
+                let path = self.host.sandboxed(&path)?;
+                fs::write(path, &bytes).map_err(Panic::WriteToFileErr)?;
+                self.stack_push(Value::Uint(bytes.len()))
+            }
+            n => Err(Panic::UnknownSyscall(n)),
+        }
+    }
+
     fn load_from_file<P: AsRef<Path>>(&mut self, path: P) -> VMResult<()> {
-        for inst_chunck in fs::read(path.as_ref())
-            .map_err(Panic::ReadFileErr)?
-            .chunks(INST_CHUNCK_SIZE)
-        {
-            self.program
-                .push(usm::deserialize(inst_chunck.try_into().unwrap()));
+        self.load_binary(&fs::read(path.as_ref()).map_err(Panic::ReadFileErr)?)
+    }
+
+    fn load_binary(&mut self, bytes: &[u8]) -> VMResult<()> {
+        let (code, data) = usm::split_data(bytes);
+        for inst in usm::disassemble_all(code) {
+            self.program.push(inst?);
+        }
+        for val in usm::disassemble_data(data) {
+            self.data.push(val?);
         }
 
         Ok(())
     }
 
-    fn disassemble_from_file<P: AsRef<Path>>(&mut self, path: P) -> VMResult<()> {
-        self.program =
-            usm::disassemble(fs::read_to_string(path.as_ref()).map_err(Panic::ReadFileErr)?)?;
+    fn disassemble_from_file<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        opts: &ParseOptions,
+    ) -> VMResult<()> {
+        self.source_text = utils::read_source(path)?;
+        (self.program, self.labels, self.data, self.source) =
+            usm::disassemble(&self.source_text, opts)?;
 
         Ok(())
     }
 
     fn save_into_file<P: AsRef<Path>>(&self, file: Option<P>) -> VMResult<()> {
-        let mut buf = Array::<SerializedInst, PROGRAM_INST_CAPACITY>::new();
-        for inst in self.program.get_all().iter() {
-            buf.push(usm::serialize(*inst));
-        }
-        let ser_prog = buf.get_all().concat();
+        let ser_prog = self
+            .program
+            .iter()
+            .map(|inst| usm::serialize(*inst))
+            .chain(self.data.iter().map(|val| usm::serialize_data(*val)))
+            .collect::<Vec<SerializedInst>>()
+            .concat();
         match file {
-            Some(f) => fs::write(f, ser_prog.as_slice()),
-            _ => io::stdout().lock().write_all(ser_prog.as_slice()),
+            Some(f) => utils::write_atomically(f, |out| out.write_all(ser_prog.as_slice())),
+            _ => io::stdout()
+                .lock()
+                .write_all(ser_prog.as_slice())
+                .map_err(Panic::WriteToFileErr),
         }
-        .map_err(Panic::WriteToFileErr)
     }
 
     fn assemble_into_file<P: AsRef<Path>>(&self, file: Option<P>) -> VMResult<()> {
-        let src = usm::assemble(self.program.get_all());
+        let src = usm::assemble_data(&self.data) + &usm::assemble(&self.program);
         match file {
-            Some(f) => fs::write(f, src.as_bytes()),
-            _ => io::stdout().lock().write_all(src.as_bytes()),
+            Some(f) => utils::write_atomically(f, |out| out.write_all(src.as_bytes())),
+            _ => io::stdout()
+                .lock()
+                .write_all(src.as_bytes())
+                .map_err(Panic::WriteToFileErr),
         }
-        .map_err(Panic::WriteToFileErr)
+    }
+
+    fn dump_to_file<P: AsRef<Path>>(
+        &self,
+        file: Option<P>,
+        inst_limit: Option<usize>,
+        page: Option<usize>,
+        find: Option<&str>,
+        float_precision: Option<usize>,
+    ) -> VMResult<()> {
+        let limit = inst_limit
+            .map(|l| if l <= self.program.len() { l } else { 0 })
+            .unwrap_or(self.program.len());
+        let program = &self.program[..limit];
+        let addrs = match find {
+            Some(needle) => utils::find_with_context(program, needle, 2),
+            _ => (0..program.len()).collect(),
+        };
+        let dump = |out: &mut dyn Write, page: Option<usize>, is_tty: bool| -> io::Result<()> {
+            let mut stdin = io::stdin().lock();
+            for (printed, addr) in addrs.iter().enumerate() {
+                if utils::page_is_full(printed, page, is_tty) {
+                    out.flush()?;
+                    stdin.read_line(&mut String::new())?;
+                }
+                if find.is_some() {
+                    write!(out, "{addr}: ")?;
+                }
+                match float_precision {
+                    Some(p) => writeln!(out, "{:.p$}", program[*addr])?,
+                    _ => writeln!(out, "{}", program[*addr])?,
+                }
+            }
+            out.flush()
+        };
+
+        match file {
+            Some(f) => utils::write_atomically(f, |out| dump(out, None, false)),
+            _ => dump(
+                &mut BufWriter::new(io::stdout().lock()),
+                page,
+                io::stdout().is_terminal(),
+            )
+            .map_err(Panic::WriteToFileErr),
+        }
+    }
+
+    fn doc_isa_into_file<P: AsRef<Path>>(file: Option<P>, markdown: bool) -> VMResult<()> {
+        let doc = utils::isa_reference(markdown);
+        match file {
+            Some(f) => utils::write_atomically(f, |out| out.write_all(doc.as_bytes())),
+            _ => io::stdout()
+                .lock()
+                .write_all(doc.as_bytes())
+                .map_err(Panic::WriteToFileErr),
+        }
+    }
+
+    fn patch(&mut self, addr: usize, inst: Instruction) -> VMResult<()> {
+        if addr >= self.program.len() {
+            return Err(Panic::InvalidOperandValue(Value::Uint(addr)));
+        }
+        self.program[addr] = inst;
+
+        Ok(())
+    }
+
+    fn dump_state(&self) -> String {
+        format!(
+            "Виконано: {n} інструкцій. Найбільше: стек [{stack_peak}], кадри [{frames_peak}], виклики [{calls_peak}], адреса пам'яті [{memory_peak}]. Стек: [{depth}] верхівка={top}",
+            n = self.inst_count,
+            stack_peak = self.stack_peak,
+            frames_peak = self.frames_peak,
+            calls_peak = self.calls_peak,
+            memory_peak = self.memory_peak.map_or("-".to_string(), |a| a.to_string()),
+            depth = self.stack.size,
+            top = self.stack_get(0).unwrap_or_default(),
+        )
     }
 
     fn execute_instruction(&mut self) -> VMResult<()> {
-        let inst = self.program.get(self.inst_ptr);
+        let inst = self.program[self.inst_ptr];
+        if !self.capabilities.allows(inst.kind.info().category) {
+            return Err(Panic::CapabilityDenied {
+                kind: inst.kind,
+                inst_ptr: self.inst_ptr,
+            });
+        }
+        self.inst_count += 1;
 
-        if inst.conditional && self.stack_pop()?.into_uint() == 0 {
+        if inst.conditional && self.stack_pop()?.into_float()? == 0.0 {
             self.inst_ptr += 1;
             return Ok(());
         }
 
-        macro_rules! math {
-            ($op:tt, $func_op:tt) => {{
-                let a = self.stack_pop()?;
-                let b = self.stack_pop()?.into_type_of(a);
-                use Value::*;
-                self.stack_push(match (a, b) {
-                    (Int(a), Int(b)) => Value::Int(b.$func_op(a).ok_or(Panic::ValueOverflow)?),
-                    (Uint(a), Uint(b)) => Value::Uint(b.$func_op(a).ok_or(Panic::ValueOverflow)?),
-                    (Float(a), Float(b)) => {
-                        let r = b $op a;
-                        if !r.is_normal() {
-                            return Err(Panic::ValueOverflow);
-                        }
-                        Value::Float(r)
-                    }
-                    // We are not allowed to push or pop Null values
-                    _ => unreachable!(),
-                })?
-            }};
-        }
-
         use InstructionKind::*;
         match inst.kind {
             Nop => {}
             Push => self.stack_push(inst.operand)?,
             Drop => _ = self.stack_pop()?,
-            Dup => self.stack_push(self.stack_get(inst.operand.into_uint())?)?,
-            Call | Jump => {
+            DropAt => {
+                let depth = inst.operand.into_uint()?;
+                if depth >= self.stack.size {
+                    return Err(Panic::InvalidOperandValue(inst.operand));
+                }
+                self.stack.remove_from_end(depth);
+            }
+            Dup => {
+                let depth = if inst.operand.is_null() {
+                    let depth = self.stack_pop()?;
+                    let d = depth.into_float()?;
+                    if d < 0.0 || d.fract() != 0.0 || d as usize >= self.stack.size {
+                        return Err(Panic::InvalidOperandValue(depth));
+                    }
+                    d as usize
+                } else {
+                    inst.operand.into_uint()?
+                };
+                self.stack_push(self.stack_get(depth)?)?
+            }
+            JumpRel => {
+                self.inst_ptr = inst
+                    .target(self.inst_ptr)
+                    .filter(|t| *t < self.program.len())
+                    .ok_or(Panic::InvalidOperandValue(inst.operand))?;
+                return Ok(());
+            }
+            // Falls through to the jump below when the condition holds
+            JumpZero | JumpNonZero
+                if (self.stack_pop()?.into_float()? == 0.0) != (inst.kind == JumpZero) => {}
+            Call | Gosub | Jump | JumpZero | JumpNonZero => {
                 if matches!(inst.kind, Call) {
                     self.stack_push(Value::Uint(self.inst_ptr + 1))?;
                 }
-                let addr = inst.operand.into_uint();
-                return (addr < self.program.size)
-                    .then(|| {
-                        self.inst_ptr = addr;
-                        Ok(())
-                    })
-                    .unwrap_or(Err(Panic::StackUnderflow));
+                if matches!(inst.kind, Gosub) {
+                    let limit = self.call_depth.unwrap_or(RETURN_STACK_CAPACITY);
+                    if self.return_stack.size >= limit.min(RETURN_STACK_CAPACITY) {
+                        return Err(Panic::ReturnStackOverflow);
+                    }
+                    self.return_stack.push(self.inst_ptr + 1);
+                    self.calls_peak = self.calls_peak.max(self.return_stack.size);
+                }
+                let addr = inst.operand.into_uint()?;
+                if addr >= self.program.len() {
+                    return Err(Panic::StackUnderflow);
+                }
+                self.inst_ptr = addr;
+                return Ok(());
             }
             NotEq | Eq => {
                 let a = self.stack_get(0)?;
                 let b = self.stack_get(1)?;
-                self.stack_push(Value::Uint(
-                    ((inst.kind == Eq) & (a == b)) as usize | (a != b) as usize,
-                ))?;
+                self.stack_push(Value::Uint(((a == b) == (inst.kind == Eq)) as usize))?;
+            }
+            Sum | Sub | Mul | Div | Mod | And | Or | Xor | Lt | Gt | Le | Ge | Cmp => {
+                let a = self.stack_pop()?;
+                let b = self.stack_pop()?;
+                self.warn_coercion(a, b);
+                self.stack_push(math(inst.kind, a, b)?)?;
+            }
+            Not => {
+                let a = self.stack_pop()?;
+                self.stack_push(match a {
+                    Value::Int(v) => Value::Int(!v),
+                    Value::Uint(v) => Value::Uint(!v),
+                    _ => return Err(Panic::InvalidOperandValue(a)),
+                })?;
+            }
+            // `_зціл` values are counted in their two's complement bits
+            PopCount | Clz | Ctz => {
+                let a = self.stack_pop()?;
+                let bits = match a {
+                    Value::Int(v) => v as usize,
+                    Value::Uint(v) => v,
+                    _ => return Err(Panic::InvalidOperandValue(a)),
+                };
+                self.stack_push(Value::Uint(match inst.kind {
+                    PopCount => bits.count_ones(),
+                    Clz => bits.leading_zeros(),
+                    _ => bits.trailing_zeros(),
+                } as usize))?;
+            }
+            // Unsigned values wrap around, so negating twice gives the value back
+            Neg => {
+                let a = self.stack_pop()?;
+                self.stack_push(match a {
+                    Value::Int(v) => Value::Int(v.checked_neg().ok_or(Panic::ValueOverflow)?),
+                    Value::Uint(v) => Value::Uint(v.wrapping_neg()),
+                    Value::Float(v) => Value::Float(-v),
+                    Value::Null => return Err(Panic::InvalidOperandValue(a)),
+                })?;
+            }
+            Abs => {
+                let a = self.stack_pop()?;
+                self.stack_push(match a {
+                    Value::Int(v) => Value::Int(v.checked_abs().ok_or(Panic::ValueOverflow)?),
+                    Value::Float(v) => Value::Float(v.abs()),
+                    Value::Uint(_) => a,
+                    Value::Null => return Err(Panic::InvalidOperandValue(a)),
+                })?;
+            }
+            // Integers are already whole, they are left as they are
+            Floor | Ceil | Round => {
+                let a = self.stack_pop()?;
+                self.stack_push(match a {
+                    Value::Float(v) => Value::Float(match inst.kind {
+                        Floor => v.floor(),
+                        Ceil => v.ceil(),
+                        _ => v.round(),
+                    }),
+                    Value::Null => return Err(Panic::InvalidOperandValue(a)),
+                    _ => a,
+                })?;
+            }
+            Sqrt => {
+                let a = self.stack_pop()?;
+                let v = a.into_float()?;
+                if v < 0.0 {
+                    return Err(Panic::FloatDomain(a));
+                }
+                self.stack_push(Value::Float(v.sqrt()))?;
+            }
+            // The result has the type of the base, so an integer base
+            // only takes whole non-negative exponents
+            Pow => {
+                let exp = self.stack_pop()?;
+                let e = exp.into_float()?;
+                let base = self.stack_pop()?;
+                let int_exp = || {
+                    if e < 0.0 || e.fract() != 0.0 {
+                        return Err(Panic::InvalidOperandValue(exp));
+                    }
+                    u32::try_from(exp.into_uint()?).map_err(|_| Panic::ValueOverflow)
+                };
+                self.stack_push(match base {
+                    Value::Int(b) => {
+                        Value::Int(b.checked_pow(int_exp()?).ok_or(Panic::ValueOverflow)?)
+                    }
+                    Value::Uint(b) => {
+                        Value::Uint(b.checked_pow(int_exp()?).ok_or(Panic::ValueOverflow)?)
+                    }
+                    Value::Float(b) => {
+                        let r = b.powf(e);
+                        if !r.is_finite() {
+                            return Err(Panic::ValueOverflow);
+                        }
+                        Value::Float(r)
+                    }
+                    Value::Null => return Err(Panic::InvalidOperandValue(base)),
+                })?;
+            }
+            // Floats lose their fraction, `до_ціл` refuses negative values instead of dropping the sign.
+            // `привести` takes 0 for `_зціл`, 1 for `_ціл` and 2 for `_дроб`.
+            ToInt | ToUint | ToFloat | Cast => {
+                let ty = match (inst.kind, inst.operand) {
+                    (ToInt, _) => 0,
+                    (ToUint, _) => 1,
+                    (ToFloat, _) => 2,
+                    (_, Value::Int(ty @ 0..=2)) => ty as usize,
+                    (_, Value::Uint(ty @ 0..=2)) => ty,
+                    _ => return Err(Panic::InvalidOperandValue(inst.operand)),
+                };
+                let a = self.stack_pop()?;
+                self.stack_push(match ty {
+                    0 => Value::Int(a.into_int()?),
+                    1 if a.into_float()? < 0.0 => return Err(Panic::ValueUnderflow),
+                    1 => Value::Uint(a.into_uint()?),
+                    _ => Value::Float(a.into_float()?),
+                })?;
+            }
+            LogicalNot => {
+                let a = self.stack_pop()?;
+                self.stack_push(Value::Uint((a.into_float()? == 0.0) as usize))?;
+            }
+            // The shift amount is a whole non-negative number that wraps around
+            // the bit width, `_зціл` values keep their sign when shifted right
+            Shl | Shr | RotL | RotR => {
+                let amount = match self.stack_pop()? {
+                    Value::Uint(n) => n,
+                    Value::Int(n) if n >= 0 => n as usize,
+                    a => return Err(Panic::InvalidOperandValue(a)),
+                } % usize::BITS as usize;
+                let amount = amount as u32;
+                let b = self.stack_pop()?;
+                self.stack_push(match (b, inst.kind) {
+                    (Value::Int(v), Shl) => Value::Int(v.wrapping_shl(amount)),
+                    (Value::Int(v), Shr) => Value::Int(v.wrapping_shr(amount)),
+                    (Value::Int(v), RotL) => Value::Int(v.rotate_left(amount)),
+                    (Value::Int(v), _) => Value::Int(v.rotate_right(amount)),
+                    (Value::Uint(v), Shl) => Value::Uint(v.wrapping_shl(amount)),
+                    (Value::Uint(v), Shr) => Value::Uint(v.wrapping_shr(amount)),
+                    (Value::Uint(v), RotL) => Value::Uint(v.rotate_left(amount)),
+                    (Value::Uint(v), _) => Value::Uint(v.rotate_right(amount)),
+                    _ => return Err(Panic::InvalidOperandValue(b)),
+                })?;
             }
-            Sum => math!(+ , checked_add),
-            Sub => math!(- , checked_sub),
-            Mul => math!(* , checked_mul),
-            Div => math!(/ , checked_div),
 
             // TBD
-            Extern => match inst.operand.into_uint() {
-                0 => println!("{}", self.stack_get(0)?),
-                _ => panic!(),
+            Extern => match inst.operand.into_uint()? {
+                0 => {
+                    let top = self.stack_get(0)?;
+                    writeln!(self.host.stdout, "{top}").map_err(Panic::WriteToFileErr)?;
+                }
+                _ => return Err(Panic::InvalidOperandValue(inst.operand)),
             },
+            Syscall => self.syscall(inst.operand)?,
+            // Unlike `ззовні 0` the printed value is consumed
+            Print | PrintNoLn => {
+                let top = self.stack_pop()?;
+                let end = if inst.kind == Print { "\n" } else { "" };
+                write!(self.host.stdout, "{top}{end}").map_err(Panic::WriteToFileErr)?;
+            }
+            PrintChar => {
+                let c = as_char(self.stack_pop()?)?;
+                write!(self.host.stdout, "{c}").map_err(Panic::WriteToFileErr)?;
+            }
+            PrintString => {
+                let string = self.pop_string()?;
+                write!(self.host.stdout, "{string}").map_err(Panic::WriteToFileErr)?;
+            }
             Return => {
-                self.inst_ptr = self.stack_pop()?.into_uint();
+                self.inst_ptr = self.stack_pop()?.into_uint()?;
+                return Ok(());
+            }
+            Retsub => {
+                if self.return_stack.size == 0 {
+                    return Err(Panic::ReturnStackUnderflow);
+                }
+                self.inst_ptr = self.return_stack.pop();
                 return Ok(());
             }
             Halt => {
-                self.inst_ptr = self.program.size;
+                self.inst_ptr = self.program.len();
                 return Ok(());
             }
+            Assert => {
+                if self.stack_pop()?.into_float()? == 0.0 {
+                    return Err(Panic::AssertionFailed {
+                        inst_ptr: self.inst_ptr,
+                    });
+                }
+            }
+            Bounds => {
+                let idx = self.stack_get(0)?;
+                let i = idx.into_float()?;
+                if i < 0.0 || i.fract() != 0.0 || i >= inst.operand.into_uint()? as f64 {
+                    return Err(Panic::InvalidOperandValue(idx));
+                }
+            }
+            // With the operand 1 the prompt string `адр n` is popped first
+            Read | ReadTry => {
+                match inst.operand {
+                    Value::Null | Value::Int(0) | Value::Uint(0) => {}
+                    Value::Int(1) | Value::Uint(1) => {
+                        let prompt = self.pop_string()?;
+                        if self.host.interactive {
+                            write!(self.host.stdout, "{prompt}")
+                                .and_then(|_| self.host.stdout.flush())
+                                .map_err(Panic::WriteToFileErr)?;
+                        }
+                    }
+                    _ => return Err(Panic::InvalidOperandValue(inst.operand)),
+                }
+                match self.host.read_token() {
+                    Ok(token) => {
+                        let value =
+                            Value::try_parse(&token).map_err(|_| Panic::InputError(token))?;
+                        self.stack_push(value)?;
+                        if inst.kind == ReadTry {
+                            self.stack_push(Value::Uint(1))?;
+                        }
+                    }
+                    Err(Panic::EndOfInput) if inst.kind == ReadTry => {
+                        self.stack_push(Value::Uint(0))?
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            ReadChar => {
+                let c = self.host.read_char().map_err(Panic::ReadFileErr)?;
+                self.stack_push(c.map_or(Value::Int(-1), |c| Value::Uint(c as usize)))?;
+            }
+            Rand => {
+                let r = self.host.next_random();
+                self.stack_push(Value::Uint(r as usize))?;
+            }
+            // Wraps around after 584 years
+            Tick => {
+                let elapsed = (self.host.clock)();
+                self.stack_push(Value::Uint(elapsed as usize))?;
+            }
+            Store => {
+                let addr = self.stack_pop()?;
+                let addr = self.memory_addr(addr)?;
+                let value = self.stack_pop()?;
+                self.touch_memory(addr + 1);
+                if addr >= self.memory.len() {
+                    self.memory.resize(addr + 1, Value::Int(0));
+                }
+                self.memory[addr] = value;
+            }
+            Load => {
+                let addr = self.stack_pop()?;
+                let addr = self.memory_addr(addr)?;
+                self.touch_memory(addr + 1);
+                let value = self.memory.get(addr).copied().unwrap_or(Value::Int(0));
+                self.stack_push(value)?;
+            }
+            ToReg => {
+                self.register(inst.operand)?;
+                self.registers[inst.operand.into_uint()?] = self.stack_pop()?;
+            }
+            FromReg => self.stack_push(self.register(inst.operand)?)?,
+            // 0 is left for `Null`, which never gets on the stack
+            TypeOf => self.stack_push(Value::Uint(match self.stack_get(0)? {
+                Value::Null => 0,
+                Value::Int(_) => 1,
+                Value::Uint(_) => 2,
+                Value::Float(_) => 3,
+            }))?,
+            Depth => self.stack_push(Value::Uint(self.stack.size))?,
+            Stats => self.stack_push(Value::Uint(match inst.operand.into_uint()? {
+                // Not counting the current one
+                0 => self.inst_count - 1,
+                1 => self.stack_peak,
+                // There is no gas metering yet
+                2 => usize::MAX,
+                _ => return Err(Panic::InvalidOperandValue(inst.operand)),
+            }))?,
+            Frame => {
+                let count = inst.operand.into_uint()?;
+                if self.frames.size == FRAMES_CAPACITY || count > LOCALS_CAPACITY - self.locals.size
+                {
+                    return Err(Panic::StackOverflow);
+                }
+                self.frames.push(self.locals.size);
+                self.frames_peak = self.frames_peak.max(self.frames.size);
+                for _ in 0..count {
+                    self.locals.push(Value::Int(0));
+                }
+            }
+            Local => {
+                let value = *self.local_mut(inst.operand)?;
+                self.stack_push(value)?;
+            }
+            LocalStore => {
+                let value = self.stack_pop()?;
+                *self.local_mut(inst.operand)? = value;
+            }
+            Leave => {
+                if self.frames.size == 0 {
+                    return Err(Panic::CallStackUnderflow);
+                }
+                self.locals.size = self.frames.pop();
+            }
+            Over => self.stack_push(self.stack_get(1)?)?,
+            Rot | RotBack => {
+                if self.stack.size < 3 {
+                    return Err(Panic::StackUnderflow);
+                }
+                let c = self.stack_pop()?;
+                let b = self.stack_pop()?;
+                let a = self.stack_pop()?;
+                let rotated = if inst.kind == Rot {
+                    [b, c, a]
+                } else {
+                    [c, a, b]
+                };
+                for v in rotated {
+                    self.stack_push(v)?;
+                }
+            }
+            Roll => {
+                let depth = inst.operand.into_uint()?;
+                if depth >= self.stack.size {
+                    return Err(Panic::InvalidOperandValue(inst.operand));
+                }
+                let value = self.stack.remove_from_end(depth);
+                self.stack.push(value);
+            }
+            Exchange => {
+                let a = self.stack_pop()?;
+                let b = self.stack_pop()?;
+                self.stack_push(a)?;
+                self.stack_push(b)?;
+            }
             Swap => {
                 if self.stack.size < 2 {
                     return Err(Panic::StackUnderflow);
                 }
-                let idx = inst.operand.into_uint();
+                let idx = inst.operand.into_uint()?;
                 let saved_top = self.stack_get(0)?;
                 let saved_target = self.stack_get(idx)?;
                 let top = self.stack_get_mut(0)?;
@@ -164,15 +1006,46 @@ impl VM {
         Ok(())
     }
 
+    fn warn_coercion(&mut self, a: Value, b: Value) {
+        if std::mem::discriminant(&a) == std::mem::discriminant(&b) {
+            return;
+        }
+        if let Some(warnings) = self.coercion_warnings.as_mut() {
+            let count = warnings.entry(self.inst_ptr).or_default();
+            if *count < COERCION_WARNINGS_PER_ADDR {
+                *count += 1;
+                // A warning that cannot be written is not worth stopping the run for
+                _ = writeln!(
+                    self.host.stderr,
+                    "УВАГА: {addr}: \"{inst}\" перетворює {b} на тип {a}",
+                    addr = self.inst_ptr,
+                    inst = self.program[self.inst_ptr],
+                );
+            }
+        }
+    }
+
+    fn local_mut(&mut self, idx: Value) -> VMResult<&mut Value> {
+        if self.frames.size == 0 {
+            return Err(Panic::CallStackUnderflow);
+        }
+        let local = self.frames.get_from_end(0) + idx.into_uint()?;
+        if local >= self.locals.size {
+            return Err(Panic::InvalidOperandValue(idx));
+        }
+
+        Ok(self.locals.get_mut(local))
+    }
+
     fn stack_get_mut(&mut self, idx: usize) -> VMResult<&mut Value> {
-        (idx <= self.stack.size)
-            .then_some(self.stack.get_from_end_mut(idx))
+        (idx < self.stack.size)
+            .then(|| self.stack.get_from_end_mut(idx))
             .ok_or(Panic::StackUnderflow)
     }
 
     fn stack_get(&self, idx: usize) -> VMResult<Value> {
-        (idx <= self.stack.size)
-            .then_some(self.stack.get_from_end(idx))
+        (idx < self.stack.size)
+            .then(|| self.stack.get_from_end(idx))
             .ok_or(Panic::StackUnderflow)
     }
 
@@ -183,19 +1056,128 @@ impl VM {
             Err(Panic::StackOverflow)
         } else {
             self.stack.push(value);
+            self.stack_peak = self.stack_peak.max(self.stack.size);
             Ok(())
         }
     }
 
     fn stack_pop(&mut self) -> VMResult<Value> {
         (self.stack.size > 0)
-            .then_some(self.stack.pop())
+            .then(|| self.stack.pop())
             .filter(|v| !v.is_null())
             .ok_or(Panic::StackUnderflow)
     }
 }
 
-fn start(config: Configuration) -> VMResult<()> {
+// Only `_ціл` values are character codes
+fn as_char(code: Value) -> VMResult<char> {
+    match code {
+        Value::Uint(c) => u32::try_from(c).ok().and_then(char::from_u32),
+        _ => None,
+    }
+    .ok_or(Panic::InvalidOperandValue(code))
+}
+
+// `a` is the top of the stack, `b` is converted to its type
+fn math(kind: InstructionKind, a: Value, b: Value) -> VMResult<Value> {
+    macro_rules! math {
+        ($op:tt, $func_op:tt) => {{
+            let b = b.into_type_of(a)?;
+            use Value::*;
+            match (a, b) {
+                (Int(a), Int(b)) => Value::Int(b.$func_op(a).ok_or(Panic::ValueOverflow)?),
+                (Uint(a), Uint(b)) => Value::Uint(b.$func_op(a).ok_or(Panic::ValueOverflow)?),
+                (Float(a), Float(b)) => {
+                    let r = b $op a;
+                    if !r.is_finite() {
+                        return Err(Panic::ValueOverflow);
+                    }
+                    Value::Float(r)
+                }
+                // We are not allowed to push or pop Null values
+                _ => unreachable!(),
+            }
+        }};
+    }
+
+    // Bits of floats are not numbers, so they are refused on either side instead of converted
+    macro_rules! bits {
+        ($op:tt) => {{
+            use Value::*;
+            if let Float(_) | Null = b {
+                return Err(Panic::InvalidOperandValue(b));
+            }
+            match (a, b.into_type_of(a)?) {
+                (Int(a), Int(b)) => Int(b $op a),
+                (Uint(a), Uint(b)) => Uint(b $op a),
+                _ => return Err(Panic::InvalidOperandValue(a)),
+            }
+        }};
+    }
+
+    // Any comparison with NaN is false
+    macro_rules! cmp {
+        ($op:tt) => {{
+            use Value::*;
+            let r = match (a, b.into_type_of(a)?) {
+                (Int(a), Int(b)) => b $op a,
+                (Uint(a), Uint(b)) => b $op a,
+                (Float(a), Float(b)) => b $op a,
+                _ => unreachable!(),
+            };
+            Uint(r as usize)
+        }};
+    }
+
+    use InstructionKind::*;
+    // A float divided by zero is left to the infinity check of `math!`
+    if matches!(kind, Div | Mod) && matches!(a, Value::Int(0) | Value::Uint(0)) {
+        return Err(Panic::DivByZero);
+    }
+
+    Ok(match kind {
+        Sum => math!(+ , checked_add),
+        Sub => math!(- , checked_sub),
+        Mul => math!(* , checked_mul),
+        Div => math!(/ , checked_div),
+        Mod => math!(% , checked_rem),
+        And => bits!(&),
+        Or => bits!(|),
+        Xor => bits!(^),
+        Lt => cmp!(<),
+        Gt => cmp!(>),
+        Le => cmp!(<=),
+        Ge => cmp!(>=),
+        // NaN has no order, so it can not be compared at all
+        Cmp => {
+            use Value::*;
+            let b = b.into_type_of(a)?;
+            let order = match (a, b) {
+                (Int(a), Int(b)) => Some(b.cmp(&a)),
+                (Uint(a), Uint(b)) => Some(b.cmp(&a)),
+                (Float(x), Float(y)) => y.partial_cmp(&x),
+                _ => unreachable!(),
+            };
+            Int(order.ok_or(Panic::InvalidOperandValue(match a {
+                Float(x) if x.is_nan() => a,
+                _ => b,
+            }))? as isize)
+        }
+        _ => unreachable!(),
+    })
+}
+
+fn parse_options(strict_types: bool, aliases_file: Option<&String>) -> VMResult<ParseOptions> {
+    Ok(ParseOptions {
+        strict_types,
+        aliases: match aliases_file {
+            Some(f) => usm::parse_aliases(utils::read_source(f)?)?,
+            _ => Default::default(),
+        },
+    })
+}
+
+fn start(config: &Configuration) -> VMResult<()> {
     let mut state = VM::default();
 
     use Configuration::*;
@@ -204,33 +1186,114 @@ fn start(config: Configuration) -> VMResult<()> {
             target_file,
             inst_limit,
             from_usm,
+            page,
+            find,
+            output_file,
+            strict_types,
+            float_precision,
+            strict_empty,
         } => {
-            if from_usm || target_file.ends_with(".usm") {
-                state.disassemble_from_file(target_file)?
+            utils::ensure_not_input(target_file, output_file.as_ref())?;
+            if *from_usm || target_file.ends_with(".usm") {
+                state.disassemble_from_file(target_file, &parse_options(*strict_types, None)?)?
             } else {
                 state.load_from_file(target_file)?;
             }
-
-            for i in 0..inst_limit
-                .map(|l| if l <= state.program.size { l } else { 0 })
-                .unwrap_or(state.program.size)
-            {
-                println!("{}", state.program.get(i));
+            if state.program.is_empty() {
+                if *strict_empty {
+                    return Err(Panic::EmptyProgram);
+                }
+                eprintln!("{EMPTY_PROGRAM_NOTICE}");
             }
+
+            state.dump_to_file(
+                output_file.as_ref(),
+                *inst_limit,
+                *page,
+                find.as_deref(),
+                *float_precision,
+            )?;
         }
         Disassemble {
             target_file,
             output_file,
+            fold,
+            inline_threshold,
+            strict_types,
+            aliases_file,
         } => {
-            state.disassemble_from_file(target_file)?;
-            state.save_into_file(output_file)?;
+            utils::ensure_not_input(target_file, output_file.as_ref())?;
+            state.disassemble_from_file(
+                target_file,
+                &parse_options(*strict_types, aliases_file.as_ref())?,
+            )?;
+            if let Some(threshold) = inline_threshold {
+                (state.program, state.source) =
+                    opt::inline_calls(&state.program, &state.source, *threshold);
+            }
+            if *fold {
+                (state.program, state.source) = opt::fold_constants(&state.program, &state.source)?;
+            }
+            state.save_into_file(output_file.as_ref())?;
         }
         Assemble {
             target_file,
             output_file,
         } => {
+            utils::ensure_not_input(target_file, output_file.as_ref())?;
             state.load_from_file(target_file)?;
-            state.assemble_into_file(output_file)?;
+            state.assemble_into_file(output_file.as_ref())?;
+        }
+        DocIsa {
+            output_file,
+            markdown,
+        } => VM::doc_isa_into_file(output_file.as_ref(), *markdown)?,
+        Patch {
+            target_file,
+            output_file,
+            sets,
+            nops,
+        } => {
+            utils::ensure_not_input(target_file, output_file.as_ref())?;
+            state.load_from_file(target_file)?;
+            for (addr, src) in sets {
+                let (inst, ..) = usm::disassemble(src, &ParseOptions::default())?;
+                if inst.len() != 1 {
+                    return Err(Panic::parse_error(format!(
+                        "очікувалась одна інструкція, а не \"{src}\""
+                    )));
+                }
+                state.patch(*addr, inst[0])?;
+            }
+            for (from, to) in nops {
+                for addr in *from..=*to {
+                    state.patch(addr, Instruction::default())?;
+                }
+            }
+
+            let program = &state.program;
+            if let Some((_, inst)) = program.iter().enumerate().find(|(addr, i)| {
+                i.kind.is_branch()
+                    && !i.operand.is_null()
+                    && i.target(*addr).is_none_or(|t| t >= program.len())
+            }) {
+                return Err(Panic::parse_error(format!(
+                    "адреса переходу інструкції \"{inst}\" за межами програми"
+                )));
+            }
+
+            state.save_into_file(Some(output_file.as_ref().unwrap_or(target_file)))?;
+        }
+        Hash {
+            target_file,
+            from_usm,
+        } => {
+            if *from_usm || target_file.ends_with(".usm") {
+                state.disassemble_from_file(target_file, &ParseOptions::default())?;
+            } else {
+                state.load_from_file(target_file)?;
+            }
+            println!("{:016x}", hash::program_hash(&state.program, &state.data));
         }
         Run {
             target_file,
@@ -238,38 +1301,140 @@ fn start(config: Configuration) -> VMResult<()> {
             inst_limit,
             debug_inst,
             debug_stack,
+            watches,
+            snapshot_at,
+            snapshot_file,
+            strict_types,
+            verbose,
+            capabilities,
+            aliases_file,
+            warn_coercion,
+            color,
+            quiet,
+            call_depth,
+            seed,
+            allow_fs,
+            fs_root,
         } => {
-            if from_usm || target_file.ends_with(".usm") {
-                state.disassemble_from_file(target_file)?;
+            state = state.with_capabilities(*capabilities);
+            state.call_depth = *call_depth;
+            state.host.fs = match fs_root {
+                _ if !*allow_fs => FsAccess::Denied,
+                Some(root) => FsAccess::Under(fs::canonicalize(root).map_err(Panic::ReadFileErr)?),
+                _ => FsAccess::Anywhere,
+            };
+            if let Some(seed) = seed {
+                state.host.seed(*seed);
+            }
+            // https://no-color.org
+            let color = *color && state.host.env("NO_COLOR").is_none_or(|v| v.is_empty());
+            if *warn_coercion {
+                state.coercion_warnings = Some(HashMap::new());
+            }
+            if *from_usm || target_file.ends_with(".usm") {
+                state.disassemble_from_file(
+                    target_file,
+                    &parse_options(*strict_types, aliases_file.as_ref())?,
+                )?;
             } else {
                 state.load_from_file(target_file)?;
             };
+            state.memory.clone_from(&state.data);
+            state.touch_memory(state.data.len());
+            if state.program.is_empty() && !*quiet {
+                eprintln!("{EMPTY_PROGRAM_NOTICE}");
+            }
+
+            let mut checkpoints = Vec::<(&str, usize)>::new();
+            for label in snapshot_at {
+                checkpoints.push((
+                    label,
+                    state.labels.get(label).ok_or(Panic::parse_error(format!(
+                        "лейбл для знімку стеку \"{label}\" не існує"
+                    )))?,
+                ));
+            }
+            let mut snapshots: Box<dyn Write> = match snapshot_file {
+                Some(f) => Box::new(BufWriter::new(
+                    fs::File::create(f).map_err(Panic::WriteToFileErr)?,
+                )),
+                _ => Box::new(io::stdout()),
+            };
 
-            let mut inst_count = 0;
+            let mut watched: Vec<Value> = watches.iter().map(|w| w.eval(&state)).collect();
             let limit = inst_limit.unwrap_or(0);
-            while state.inst_ptr < state.program.size {
-                if limit != 0 && inst_count == limit {
+            while state.inst_ptr < state.program.len() {
+                if limit != 0 && state.inst_count == limit {
                     break;
                 }
-                if debug_inst {
+                if *debug_inst {
                     println!(
                         "+ ІНСТ {ptr} : {inst}",
                         ptr = state.inst_ptr,
-                        inst = state.program.get(state.inst_ptr),
+                        inst = state.program[state.inst_ptr],
                     );
                 }
 
-                state.execute_instruction()?;
-                inst_count += 1;
+                let addr = state.inst_ptr;
+                for (label, _) in checkpoints.iter().filter(|c| c.1 == addr) {
+                    writeln!(
+                        snapshots,
+                        "ЗНІМОК {label} крок {step} [{size}] : {stack}",
+                        step = state.inst_count,
+                        size = state.stack.size,
+                        stack = state.stack,
+                    )
+                    .map_err(Panic::WriteToFileErr)?;
+                }
+
+                if let Err(e) = state.execute_instruction() {
+                    eprint!("{}", utils::render_panic_context(&state, &e));
+                    return Err(e);
+                }
+
+                for (expr, last) in watches.iter().zip(watched.iter_mut()) {
+                    let new = expr.eval(&state);
+                    if new != *last {
+                        println!(
+                            "СТЕЖ {expr} крок {step} адреса {addr} : {last} -> {new}",
+                            step = state.inst_count
+                        );
+                        *last = new;
+                    }
+                }
 
-                if debug_stack {
+                if *debug_stack {
+                    let top = state.stack_get(0).unwrap_or_default();
                     println!(
                         "СТЕК [{size}] : {v}",
                         size = state.stack.size,
-                        v = state.stack.get_last()
+                        v = if color {
+                            utils::colorize(top)
+                        } else {
+                            top.to_string()
+                        }
                     );
+                    if state.program[addr].kind == InstructionKind::ToReg {
+                        let registers = (0..REGISTERS_COUNT)
+                            .map(|i| {
+                                state
+                                    .register(Value::Uint(i))
+                                    .unwrap_or_default()
+                                    .to_string()
+                            })
+                            .collect::<Vec<_>>();
+                        println!("РЕГІСТРИ : {}", registers.join(" "));
+                    }
+                    if state.program[addr].kind == InstructionKind::Store {
+                        println!("ПАМ'ЯТЬ [{}/{MEMORY_CAPACITY}]", state.memory.len());
+                    }
                 }
             }
+            snapshots.flush().map_err(Panic::WriteToFileErr)?;
+
+            if *verbose {
+                println!("{}", state.dump_state());
+            }
         }
     }
 
@@ -282,6 +1447,12 @@ enum Configuration {
         target_file: String,
         inst_limit: Option<usize>,
         from_usm: bool,
+        page: Option<usize>,
+        find: Option<String>,
+        output_file: Option<String>,
+        strict_types: bool,
+        float_precision: Option<usize>,
+        strict_empty: bool,
     },
     Run {
         target_file: String,
@@ -289,6 +1460,20 @@ enum Configuration {
         inst_limit: Option<usize>,
         debug_inst: bool,
         debug_stack: bool,
+        watches: Vec<WatchExpr>,
+        snapshot_at: Vec<String>,
+        snapshot_file: Option<String>,
+        strict_types: bool,
+        verbose: bool,
+        capabilities: Capabilities,
+        aliases_file: Option<String>,
+        warn_coercion: bool,
+        color: bool,
+        quiet: bool,
+        call_depth: Option<usize>,
+        seed: Option<u64>,
+        allow_fs: bool,
+        fs_root: Option<String>,
     },
     Assemble {
         target_file: String,
@@ -297,7 +1482,32 @@ enum Configuration {
     Disassemble {
         target_file: String,
         output_file: Option<String>,
+        fold: bool,
+        inline_threshold: Option<usize>,
+        strict_types: bool,
+        aliases_file: Option<String>,
     },
+    DocIsa {
+        output_file: Option<String>,
+        markdown: bool,
+    },
+    Hash {
+        target_file: String,
+        from_usm: bool,
+    },
+    Patch {
+        target_file: String,
+        output_file: Option<String>,
+        sets: Vec<(usize, String)>,
+        nops: Vec<(usize, usize)>,
+    },
+}
+
+macro_rules! fail {
+    ($($arg:tt)*) => {{
+        eprintln!($($arg)*);
+        std::process::exit(1)
+    }};
 }
 
 fn main() {
@@ -307,7 +1517,7 @@ fn main() {
         _ => return utils::print_usage(""),
     };
 
-    if args.len() < 1 {
+    if args.len() < 1 && sub != "doc-isa" {
         return utils::print_usage(sub);
     }
 
@@ -317,24 +1527,53 @@ fn main() {
             let mut target_file = String::new();
             let mut inst_limit: Option<usize> = None;
             let mut from_usm = false;
+            let mut page: Option<usize> = None;
+            let mut find: Option<String> = None;
+            let mut output_file: Option<String> = None;
+            let mut strict_types = false;
+            let mut float_precision: Option<usize> = None;
+            let mut strict_empty = false;
             while let Some(arg) = args.next() {
                 match arg.as_str() {
                     "-usm" => from_usm = true,
+                    "--strict-empty" => strict_empty = true,
+                    "--strict-types" => strict_types = true,
+                    "--float-precision" => match args.next() {
+                        Some(p) => match p.parse::<usize>() {
+                            Ok(p) => float_precision = Some(p),
+                            _ => fail!("ПОМИЛКА: Встановлена неправельна точність: {p}"),
+                        },
+                        _ => fail!("ПОМИЛКА: Значення для точності не вказано"),
+                    },
                     "-h" => return utils::print_usage(sub),
+                    "-o" => output_file = args.next(),
+                    "--page" => match args.next() {
+                        Some(size) => match size.parse::<usize>() {
+                            Ok(s) if s > 0 => page = Some(s),
+                            _ => {
+                                fail!("ПОМИЛКА: Встановлений неправельний розмір сторінки: {size}")
+                            }
+                        },
+                        _ => fail!("ПОМИЛКА: Розмір сторінки не вказано"),
+                    },
+                    "--find" => match args.next() {
+                        Some(needle) => find = Some(needle),
+                        _ => fail!("ПОМИЛКА: Значення для пошуку не вказано"),
+                    },
                     "-l" => match args.next() {
                         Some(limit) => match limit.parse::<usize>() {
                             Ok(l) => inst_limit = Some(l),
-                            _ => return eprintln!("ПОМИЛКА: Встановлений неправельний ліміт"),
+                            _ => fail!("ПОМИЛКА: Встановлений неправельний ліміт"),
                         },
 
-                        _ => return eprintln!("ПОМИЛКА: Значення для ліміту не вказано"),
+                        _ => fail!("ПОМИЛКА: Значення для ліміту не вказано"),
                     },
                     f if Path::new(&f).is_file() => target_file = f.to_string(),
                     wrong_op if wrong_op.starts_with('-') => {
-                        return eprintln!("ПОМИЛКА: Вказана помилкова опція: {wrong_op}")
+                        fail!("ПОМИЛКА: Вказана помилкова опція: {wrong_op}")
                     }
                     wrong_file => {
-                        return eprintln!("ПОМИЛКА: Вказано неіснуючий файл: {wrong_file}")
+                        fail!("ПОМИЛКА: Вказано неіснуючий файл: {wrong_file}")
                     }
                 }
             }
@@ -343,21 +1582,44 @@ fn main() {
                 target_file,
                 inst_limit,
                 from_usm,
+                page,
+                find,
+                output_file,
+                strict_types,
+                float_precision,
+                strict_empty,
             }
         }
         "usm" | "dusm" => {
             let mut target_file = String::new();
             let mut output_file: Option<String> = None;
+            let mut fold = false;
+            let mut inline_threshold: Option<usize> = None;
+            let mut strict_types = false;
+            let mut aliases_file: Option<String> = None;
             while let Some(arg) = args.next() {
                 match arg.as_str() {
+                    "--strict-types" if sub == "dusm" => strict_types = true,
+                    "--aliases" if sub == "dusm" => aliases_file = args.next(),
+                    "--float-precision" => fail!(
+                        "ПОМИЛКА: --float-precision не підтримується для {sub}, бо результат має перекладатися без втрат"
+                    ),
                     "-h" => return utils::print_usage(sub),
                     "-o" => output_file = args.next(),
+                    "--fold" | "-O2" if sub == "dusm" => fold = true,
+                    "--inline-threshold" if sub == "dusm" => match args.next() {
+                        Some(n) => match n.parse::<usize>() {
+                            Ok(n) => inline_threshold = Some(n),
+                            _ => fail!("ПОМИЛКА: Встановлений неправельний поріг вбудовування: {n}"),
+                        },
+                        _ => fail!("ПОМИЛКА: Поріг вбудовування не вказано"),
+                    },
                     f if Path::new(&f).is_file() => target_file = f.into(),
                     wrong_op if wrong_op.starts_with('-') => {
-                        return eprintln!("ПОМИЛКА: Вказана помилкова опція: {wrong_op}")
+                        fail!("ПОМИЛКА: Вказана помилкова опція: {wrong_op}")
                     }
                     wrong_file => {
-                        return eprintln!("ПОМИЛКА: Вказано неіснуючий файл: {wrong_file}")
+                        fail!("ПОМИЛКА: Вказано неіснуючий файл: {wrong_file}")
                     }
                 }
             }
@@ -371,6 +1633,10 @@ fn main() {
                 Configuration::Disassemble {
                     target_file,
                     output_file,
+                    fold,
+                    inline_threshold,
+                    strict_types,
+                    aliases_file,
                 }
             }
         }
@@ -381,30 +1647,91 @@ fn main() {
             let mut debug_inst = false;
             let mut debug_stack = false;
             let mut from_usm = false;
+            let mut watches = Vec::<WatchExpr>::new();
+            let mut snapshot_at = Vec::<String>::new();
+            let mut snapshot_file: Option<String> = None;
+            let mut strict_types = false;
+            let mut verbose = false;
+            let mut capabilities = Capabilities::default();
+            let mut aliases_file: Option<String> = None;
+            let mut warn_coercion = false;
+            let mut color = false;
+            let mut quiet = false;
+            let mut call_depth: Option<usize> = None;
+            let mut seed: Option<u64> = None;
+            let mut allow_fs = false;
+            let mut fs_root: Option<String> = None;
 
             while let Some(a) = args.next() {
                 match a.as_str() {
                     "-usm" => from_usm = true,
+                    "--watch-expr" => match args.next().map(WatchExpr::try_parse) {
+                        Some(Ok(expr)) => watches.push(expr),
+                        Some(Err(e)) => fail!("ПОМИЛКА: {e}"),
+                        _ => fail!("ПОМИЛКА: Вираз для стеження не вказано"),
+                    },
+                    "--snapshot-at" => match args.next() {
+                        Some(labels) => snapshot_at.extend(labels.split(',').map(String::from)),
+                        _ => fail!("ПОМИЛКА: Лейбли для знімків стеку не вказано"),
+                    },
+                    "--snapshot-file" => snapshot_file = args.next(),
+                    "--aliases" => aliases_file = args.next(),
+                    "--warn-coercion" => warn_coercion = true,
+                    "--color" => color = true,
+                    "-q" => quiet = true,
+                    "--seed" => match args.next() {
+                        Some(s) => match s.parse::<u64>() {
+                            Ok(s) => seed = Some(s),
+                            _ => fail!("ПОМИЛКА: Встановлене неправильне зерно: {s}"),
+                        },
+                        _ => fail!("ПОМИЛКА: Зерно не вказано"),
+                    },
+                    "--call-depth" => match args.next() {
+                        Some(depth) => match depth.parse::<usize>() {
+                            Ok(d) => call_depth = Some(d),
+                            _ => {
+                                fail!("ПОМИЛКА: Встановлена неправильна глибина викликів: {depth}")
+                            }
+                        },
+                        _ => fail!("ПОМИЛКА: Глибину викликів не вказано"),
+                    },
+                    "--strict-types" => strict_types = true,
+                    "--allow-fs" => allow_fs = true,
+                    "--fs-root" => match args.next() {
+                        Some(dir) if Path::new(&dir).is_dir() => fs_root = Some(dir),
+                        Some(dir) => fail!("ПОМИЛКА: Вказано неіснуючий каталог: {dir}"),
+                        _ => fail!("ПОМИЛКА: Каталог для файлів не вказано"),
+                    },
+                    "--deny" => match args.next() {
+                        Some(categories) => {
+                            for c in categories.split(',') {
+                                match Category::try_parse(c) {
+                                    Ok(c) => capabilities = capabilities.deny(c),
+                                    _ => fail!("ПОМИЛКА: Вказана невідома категорія: {c}"),
+                                }
+                            }
+                        }
+                        _ => fail!("ПОМИЛКА: Категорії для заборони не вказано"),
+                    },
                     "-h" => return utils::print_usage(sub),
                     "-ds" => debug_stack = true,
+                    "-v" => verbose = true,
                     "-di" => debug_inst = true,
                     "-l" => match args.next() {
                         Some(limit) => match limit.parse::<usize>() {
                             Ok(l) => inst_limit = Some(l),
                             _ => {
-                                return eprintln!(
-                                    "ПОМИЛКА: Встановлений неправельний ліміт: {limit}"
-                                )
+                                fail!("ПОМИЛКА: Встановлений неправельний ліміт: {limit}")
                             }
                         },
-                        _ => return eprintln!("ПОМИЛКА: Значення для ліміту не вказано"),
+                        _ => fail!("ПОМИЛКА: Значення для ліміту не вказано"),
                     },
                     f if Path::new(&f).is_file() => target_file = f.into(),
                     wrong_op if wrong_op.starts_with('-') => {
-                        return eprintln!("ПОМИЛКА: Вказана помилкова опція: {wrong_op}")
+                        fail!("ПОМИЛКА: Вказана помилкова опція: {wrong_op}")
                     }
                     wrong_file => {
-                        return eprintln!("ПОМИЛКА: Вказано неіснуючий файл: {wrong_file}")
+                        fail!("ПОМИЛКА: Вказано неіснуючий файл: {wrong_file}")
                     }
                 }
             }
@@ -415,16 +1742,320 @@ fn main() {
                 inst_limit,
                 debug_inst,
                 debug_stack,
+                watches,
+                snapshot_at,
+                snapshot_file,
+                strict_types,
+                verbose,
+                capabilities,
+                aliases_file,
+                warn_coercion,
+                color,
+                quiet,
+                call_depth,
+                seed,
+                allow_fs,
+                fs_root,
+            }
+        }
+        "patch" => {
+            let mut target_file = String::new();
+            let mut output_file: Option<String> = None;
+            let mut sets = Vec::<(usize, String)>::new();
+            let mut nops = Vec::<(usize, usize)>::new();
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "-h" => return utils::print_usage(sub),
+                    "-o" => output_file = args.next(),
+                    "--set" => match (args.next(), args.next()) {
+                        (Some(addr), Some(inst)) => match addr.parse::<usize>() {
+                            Ok(addr) => sets.push((addr, inst)),
+                            _ => fail!("ПОМИЛКА: Вказана неправельна адреса: {addr}"),
+                        },
+                        _ => fail!("ПОМИЛКА: Адресу та інструкцію для заміни не вказано"),
+                    },
+                    "--nop" => match args.next() {
+                        Some(range) => match range
+                            .split_once("..")
+                            .map(|(a, b)| (a.parse::<usize>(), b.parse::<usize>()))
+                        {
+                            Some((Ok(from), Ok(to))) if from <= to => nops.push((from, to)),
+                            _ => fail!("ПОМИЛКА: Вказаний неправельний діапазон адрес: {range}"),
+                        },
+                        _ => fail!("ПОМИЛКА: Діапазон адрес не вказано"),
+                    },
+                    f if Path::new(&f).is_file() => target_file = f.into(),
+                    wrong_op if wrong_op.starts_with('-') => {
+                        fail!("ПОМИЛКА: Вказана помилкова опція: {wrong_op}")
+                    }
+                    wrong_file => fail!("ПОМИЛКА: Вказано неіснуючий файл: {wrong_file}"),
+                }
+            }
+
+            Configuration::Patch {
+                target_file,
+                output_file,
+                sets,
+                nops,
+            }
+        }
+        "hash" => {
+            let mut target_file = String::new();
+            let mut from_usm = false;
+            for arg in args {
+                match arg.as_str() {
+                    "-usm" => from_usm = true,
+                    "-h" => return utils::print_usage(sub),
+                    f if Path::new(&f).is_file() => target_file = f.into(),
+                    wrong_op if wrong_op.starts_with('-') => {
+                        fail!("ПОМИЛКА: Вказана помилкова опція: {wrong_op}")
+                    }
+                    wrong_file => fail!("ПОМИЛКА: Вказано неіснуючий файл: {wrong_file}"),
+                }
+            }
+
+            Configuration::Hash {
+                target_file,
+                from_usm,
+            }
+        }
+        "doc-isa" => {
+            let mut output_file: Option<String> = None;
+            let mut markdown = false;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "-h" => return utils::print_usage(sub),
+                    "-o" => output_file = args.next(),
+                    "--format" => match args.next().as_deref() {
+                        Some("text") => markdown = false,
+                        Some("md") => markdown = true,
+                        Some(wrong) => {
+                            fail!("ПОМИЛКА: Вказаний невідомий формат: {wrong}")
+                        }
+                        _ => fail!("ПОМИЛКА: Формат не вказано"),
+                    },
+                    wrong_op => fail!("ПОМИЛКА: Вказана помилкова опція: {wrong_op}"),
+                }
+            }
+
+            Configuration::DocIsa {
+                output_file,
+                markdown,
             }
         }
         "-h" => return utils::print_usage(""),
         wrong_sub if !wrong_sub.starts_with('-') => {
-            return eprintln!("ПОМИЛКА: Вказана помилкова підкоманда: {wrong_sub}")
+            fail!("ПОМИЛКА: Вказана помилкова підкоманда: {wrong_sub}")
         }
-        wrong_file => return eprintln!("ПОМИЛКА: Вказано неіснуючий файл: {wrong_file}"),
+        wrong_file => fail!("ПОМИЛКА: Вказано неіснуючий файл: {wrong_file}"),
     };
 
-    if let Err(e) = start(config) {
-        eprintln!("{e}");
+    if let Err(e) = start(&config) {
+        fail!("{e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUM: &[u8] = include_bytes!("../tests/fixtures/sum.bin");
+
+    fn run(vm: &mut VM) -> VMResult<()> {
+        while vm.inst_ptr < vm.program.len() {
+            vm.execute_instruction()?;
+        }
+        Ok(())
+    }
+
+    fn final_stack(src: &str, host: HostServices) -> Vec<Value> {
+        let mut vm = VM {
+            program: usm::disassemble(src, &ParseOptions::default()).unwrap().0,
+            host,
+            ..Default::default()
+        };
+        run(&mut vm).unwrap();
+        vm.stack.get_all().to_vec()
+    }
+
+    #[test]
+    fn deterministic_host_repeats_the_run() {
+        let src = "випадк\nтік\nтік\nчитай\nчитайз\nвипадк";
+        let first = final_stack(src, HostServices::deterministic(7, "12 ж"));
+        assert_eq!(
+            first,
+            final_stack(src, HostServices::deterministic(7, "12 ж"))
+        );
+        assert_eq!(first.len(), 6);
+        assert_eq!(
+            first[1..5],
+            [
+                Value::Uint(1),
+                Value::Uint(2),
+                Value::Int(12),
+                Value::Uint('ж' as usize)
+            ]
+        );
+        assert_ne!(first[0], first[5]);
+
+        let reseeded = final_stack(src, HostServices::deterministic(8, "12 ж"));
+        assert_ne!(first[0], reseeded[0]);
+    }
+
+    fn read_error(src: &str, input: &str) -> Panic {
+        let mut vm = VM {
+            program: usm::disassemble(src, &ParseOptions::default()).unwrap().0,
+            host: HostServices::deterministic(0, input),
+            ..Default::default()
+        };
+        run(&mut vm).unwrap_err()
+    }
+
+    #[test]
+    fn reading_stops_at_the_end_of_the_input() {
+        assert!(matches!(read_error("читай", ""), Panic::EndOfInput));
+        assert!(matches!(
+            read_error("читай\nчитай", " 1 \n"),
+            Panic::EndOfInput
+        ));
+        assert_eq!(
+            final_stack("читай_спроба", HostServices::deterministic(0, "")),
+            [Value::Uint(0)]
+        );
+        assert_eq!(
+            final_stack(
+                "читай_спроба\nчитай_спроба\nчитай_спроба",
+                HostServices::deterministic(0, "7_ціл\n")
+            ),
+            [
+                Value::Uint(7),
+                Value::Uint(1),
+                Value::Uint(0),
+                Value::Uint(0)
+            ]
+        );
+    }
+
+    #[test]
+    fn long_token_is_skipped_and_refused() {
+        let long = "1".repeat(READ_TOKEN_CAPACITY + 1);
+        assert!(matches!(read_error("читай", &long), Panic::InputTooLong));
+        assert!(matches!(
+            read_error("читай_спроба", &format!("{long} 5")),
+            Panic::InputTooLong
+        ));
+        // The longest allowed token is still read
+        let longest = format!("0.{}", "5".repeat(READ_TOKEN_CAPACITY - 2));
+        assert_eq!(
+            final_stack("читай", HostServices::deterministic(0, &longest)),
+            [Value::Float(0.5555555555555556)]
+        );
+    }
+
+    #[test]
+    fn prompt_is_printed_only_to_a_terminal() {
+        #[derive(Clone, Default)]
+        struct Shared(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+        impl Write for Shared {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.lock().unwrap().write(buf)
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let src = "дані п: \"число? \"\nклади п\nклади 7\nчитай 1";
+        for interactive in [false, true] {
+            let out = Shared::default();
+            let mut host = HostServices::deterministic(0, "3");
+            host.stdout = Box::new(out.clone());
+            host.interactive = interactive;
+            let (program, _, data, _) = usm::disassemble(src, &ParseOptions::default()).unwrap();
+            let mut vm = VM {
+                program,
+                memory: data,
+                host,
+                ..Default::default()
+            };
+            run(&mut vm).unwrap();
+            assert_eq!(vm.stack.get_all(), [Value::Int(3)]);
+            let printed = String::from_utf8(out.0.lock().unwrap().clone()).unwrap();
+            assert_eq!(printed, if interactive { "число? " } else { "" });
+        }
+    }
+
+    #[test]
+    fn deterministic_host_has_no_environment() {
+        assert_eq!(HostServices::deterministic(0, "").env("PATH"), None);
+    }
+
+    #[test]
+    fn start_leaves_the_configuration_to_the_caller() {
+        let dir = std::env::temp_dir().join(format!("uvm-config-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let config = Configuration::Hash {
+            target_file: "tests/fixtures/sum.usm".to_string(),
+            from_usm: true,
+        };
+        start(&config).unwrap();
+        start(&config).unwrap();
+        assert!(
+            matches!(&config, Configuration::Hash { target_file, .. } if target_file.ends_with("sum.usm"))
+        );
+
+        let config = Configuration::DocIsa {
+            output_file: Some(dir.join("isa.md").to_string_lossy().into_owned()),
+            markdown: true,
+        };
+        start(&config).unwrap();
+        let Configuration::DocIsa {
+            output_file: Some(path),
+            ..
+        } = &config
+        else {
+            unreachable!()
+        };
+        assert!(fs::read_to_string(path).unwrap().starts_with("| Мнемоніка"));
+        _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn load_binary_decodes_embedded_bytecode() {
+        let mut vm = VM::default();
+        vm.load_binary(SUM).unwrap();
+        let (program, ..) = usm::disassemble(
+            fs::read_to_string("tests/fixtures/sum.usm").unwrap(),
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(vm.program.len(), 3);
+        assert_eq!(usm::assemble(&vm.program), usm::assemble(&program));
+
+        run(&mut vm).unwrap();
+        assert_eq!(vm.stack.get_all(), [Value::Uint(5)]);
+    }
+
+    #[test]
+    fn load_binary_appends_to_the_program() {
+        let mut vm = VM::default();
+        vm.load_binary(SUM).unwrap();
+        vm.load_binary(&SUM[..usm::INST_CHUNCK_SIZE]).unwrap();
+        assert_eq!(vm.program.len(), 4);
+        assert_eq!(vm.program[3].kind, InstructionKind::Push);
+    }
+
+    #[test]
+    fn load_binary_of_nothing_is_an_empty_program() {
+        let mut vm = VM::default();
+        vm.load_binary(&[]).unwrap();
+        assert!(vm.program.is_empty());
+    }
+
+    #[test]
+    fn load_binary_refuses_a_truncated_chunk() {
+        let mut vm = VM::default();
+        let err = vm.load_binary(&SUM[..SUM.len() - 1]).unwrap_err();
+        assert!(matches!(err, Panic::CorruptedBytecode(_)), "{err}");
     }
 }
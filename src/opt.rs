@@ -0,0 +1,237 @@
+use crate::{math, usm::SourceInfo, Instruction, InstructionKind, Panic, Value};
+
+type Program = Vec<Instruction>;
+
+fn jump_targets(program: &[Instruction], source: &[SourceInfo]) -> Vec<bool> {
+    let mut targets = vec![false; program.len() + 1];
+    for (addr, (inst, info)) in program.iter().zip(source).enumerate() {
+        use InstructionKind::*;
+        let target = if inst.kind.is_branch() {
+            inst.target(addr)
+        } else if info.code_operand {
+            // Jumped to through the stack, by `вертай` or `таблиця`
+            inst.operand.into_uint().ok()
+        } else {
+            None
+        };
+        if let Some(t) = target.and_then(|t| targets.get_mut(t)) {
+            *t = true;
+        }
+        // Return address saved by the call
+        if matches!(inst.kind, Call | Gosub) {
+            targets[addr + 1] = true;
+        }
+    }
+
+    targets
+}
+
+// Moves the branches and the operands holding addresses of the code to the new addresses,
+// `origins` has the old address of every instruction that is left where it was in the source
+fn relocate(
+    program: &mut [Instruction],
+    source: &[SourceInfo],
+    origins: &[Option<usize>],
+    new_addrs: &[usize],
+) {
+    for (new_addr, origin) in origins.iter().enumerate() {
+        let Some(addr) = *origin else {
+            continue;
+        };
+        let inst = &mut program[new_addr];
+        if inst.kind.is_branch() {
+            if let Some(t) = inst.target(addr).and_then(|t| new_addrs.get(t)) {
+                inst.retarget(new_addr, *t);
+            }
+        } else if source[new_addr].code_operand {
+            if let Some(t) = inst.operand.into_uint().ok().and_then(|t| new_addrs.get(t)) {
+                inst.operand = Value::Uint(*t);
+            }
+        }
+    }
+}
+
+fn is_foldable(inst: &Instruction, info: &SourceInfo) -> bool {
+    use InstructionKind::*;
+    !inst.conditional
+        && !info.code_operand
+        && matches!(
+            inst.kind,
+            Push | Sum | Sub | Mul | Div | Mod | And | Or | Xor | Lt | Gt | Le | Ge | Cmp
+        )
+}
+
+// Replaces straight-line runs of pushes and arithmetic with the pushes of their results.
+// A run is broken by any other instruction, any conditional one and any jump target,
+// so the folded values are never read by anything but the folded instructions.
+// The source info is moved along with the instructions.
+pub fn fold_constants(
+    program: &Program,
+    source: &[SourceInfo],
+) -> Result<(Program, Vec<SourceInfo>), Panic> {
+    let targets = jump_targets(program, source);
+    let mut folded = Program::new();
+    let mut folded_source = Vec::<SourceInfo>::new();
+    let mut origins = Vec::<Option<usize>>::new();
+    let mut new_addrs = Vec::<usize>::with_capacity(program.len() + 1);
+    // Values computed so far along with the source of the instruction that computed them
+    let mut pending = Vec::<(Value, SourceInfo)>::new();
+
+    macro_rules! flush {
+        () => {
+            for (val, info) in pending.drain(..) {
+                folded.push(Instruction {
+                    kind: InstructionKind::Push,
+                    operand: val,
+                    conditional: false,
+                });
+                folded_source.push(info);
+                origins.push(None);
+            }
+        };
+    }
+    macro_rules! emit {
+        ($addr:expr, $inst:expr, $info:expr) => {
+            folded.push(*$inst);
+            folded_source.push(*$info);
+            origins.push(Some($addr));
+        };
+    }
+
+    for (addr, (inst, info)) in program.iter().zip(source).enumerate() {
+        if targets[addr] {
+            flush!();
+        }
+        new_addrs.push(folded.len() + pending.len());
+
+        if !is_foldable(inst, info) {
+            flush!();
+            emit!(addr, inst, info);
+            continue;
+        }
+
+        match inst.kind {
+            InstructionKind::Push => pending.push((inst.operand, *info)),
+            kind if pending.len() >= 2 => {
+                let (a, _) = pending.pop().unwrap();
+                let (b, _) = pending.pop().unwrap();
+                let val = math(kind, a, b).map_err(|e| {
+                    info.error(format!(
+                        "помилка \"{e}\" при обчисленні інструкції \"{inst}\""
+                    ))
+                })?;
+                pending.push((val, *info));
+            }
+            _ => {
+                flush!();
+                emit!(addr, inst, info);
+            }
+        }
+    }
+    flush!();
+    new_addrs.push(folded.len());
+    relocate(&mut folded, &folded_source, &origins, &new_addrs);
+
+    Ok((folded, folded_source))
+}
+
+// Length of the routine at `entry` without its closing `end` (`вертай` or `повернись`),
+// if it can be inlined: no calls, returns or addresses of the code inside
+// and every jump stays within the routine
+fn inlinable_len(
+    source: &[Instruction],
+    info: &[SourceInfo],
+    entry: usize,
+    end: InstructionKind,
+    threshold: usize,
+) -> Option<usize> {
+    use InstructionKind::*;
+    let len = source.get(entry..)?.iter().position(|i| i.kind == end)?;
+    let body = &source[entry..entry + len];
+    let inlinable = len <= threshold
+        && !source[entry + len].conditional
+        && !info[entry..entry + len].iter().any(|i| i.code_operand)
+        && body.iter().enumerate().all(|(n, i)| match i.kind {
+            Call | Return | Gosub | Retsub => false,
+            kind if kind.is_jump() => i
+                .target(entry + n)
+                .is_some_and(|t| (entry..=entry + len).contains(&t)),
+            _ => true,
+        });
+
+    inlinable.then_some(len)
+}
+
+// Replaces unconditional calls of routines no longer than `threshold` instructions with
+// a copy of the routine. A `клич` copy still gets its return address pushed, and the closing
+// `вертай` becomes `кинь`, so the stack looks the same as with the call. A `виклик` copy is
+// just the body, its return address never was on the stack.
+// Like `fold_constants`, only the branches and the label addresses are moved to the new addresses.
+pub fn inline_calls(
+    program: &Program,
+    info: &[SourceInfo],
+    threshold: usize,
+) -> (Program, Vec<SourceInfo>) {
+    use InstructionKind::*;
+    let source = program.as_slice();
+    let mut inlined = Program::new();
+    let mut inlined_info = Vec::<SourceInfo>::new();
+    // Old address of every instruction that is not a part of an inlined copy
+    let mut origins = Vec::<Option<usize>>::new();
+    let mut new_addrs = Vec::<usize>::with_capacity(source.len() + 1);
+
+    for (addr, inst) in source.iter().enumerate() {
+        new_addrs.push(inlined.len());
+        let end = match inst.kind {
+            Call => Return,
+            _ => Retsub,
+        };
+        let routine = (matches!(inst.kind, Call | Gosub) && !inst.conditional)
+            .then(|| inst.target(addr))
+            .flatten()
+            .and_then(|entry| Some((entry, inlinable_len(source, info, entry, end, threshold)?)));
+
+        let Some((entry, len)) = routine else {
+            inlined.push(*inst);
+            inlined_info.push(info[addr]);
+            origins.push(Some(addr));
+            continue;
+        };
+
+        let pushes_addr = inst.kind == Call;
+        let start = inlined.len() + pushes_addr as usize;
+        if pushes_addr {
+            inlined.push(Instruction {
+                kind: Push,
+                operand: Value::Uint(start + len + 1),
+                conditional: false,
+            });
+            let mut ret_info = info[addr];
+            ret_info.code_operand = true;
+            inlined_info.push(ret_info);
+        }
+        for (n, body_inst) in source[entry..entry + len].iter().enumerate() {
+            let mut body_inst = *body_inst;
+            if let Some(t) = body_inst
+                .target(entry + n)
+                .filter(|_| body_inst.kind.is_jump())
+            {
+                body_inst.retarget(start + n, start + t - entry);
+            }
+            inlined.push(body_inst);
+            inlined_info.push(info[entry + n]);
+        }
+        if pushes_addr {
+            inlined.push(Instruction {
+                kind: Drop,
+                ..Default::default()
+            });
+            inlined_info.push(info[entry + len]);
+        }
+        origins.resize(inlined.len(), None);
+    }
+    new_addrs.push(inlined.len());
+    relocate(&mut inlined, &inlined_info, &origins, &new_addrs);
+
+    (inlined, inlined_info)
+}
@@ -1,8 +1,27 @@
-use crate::{Array, Panic, PROGRAM_INST_CAPACITY};
+use crate::{Panic, MEMORY_CAPACITY};
+use std::collections::HashMap;
 
 pub const INST_CHUNCK_SIZE: usize = 10;
 pub type SerializedInst = [u8; INST_CHUNCK_SIZE];
 const COMMENT_TOKEN: &str = ";;";
+const STRICT_TYPES_DIRECTIVE: &str = "!строгі_типи";
+const JUMP_TABLE_TOKEN: &str = "таблиця";
+const DATA_DIRECTIVE: &str = "дані";
+// Name of the single `дані` directive that holds the data of the assembled bytecode
+const ASSEMBLED_DATA_NAME: &str = "дані_програми";
+// Kind byte of the chunks with the values of the `дані` directives
+pub const DATA_CHUNK: u8 = u8::MAX;
+// `@лейбл` expands to the offset of the label from the instruction
+const RELATIVE_LABEL_PREFIX: char = '@';
+// Predefined names of the tags pushed by `тип` and of the file syscalls,
+// they can not be used as labels
+const PREDEFINED_NAMES: [(&str, Value); 5] = [
+    ("ТИП_ЗЦІЛ", Value::Uint(1)),
+    ("ТИП_ЦІЛ", Value::Uint(2)),
+    ("ТИП_ДРОБ", Value::Uint(3)),
+    ("файл_читати", Value::Uint(2)),
+    ("файл_писати", Value::Uint(3)),
+];
 
 #[derive(Copy, Clone, Debug, Default, PartialEq)]
 pub enum Value {
@@ -14,7 +33,7 @@ pub enum Value {
 }
 
 impl Value {
-    fn try_parse<T: AsRef<str>>(token: T) -> Result<Self, ()> {
+    pub fn try_parse<T: AsRef<str>>(token: T) -> Result<Self, ()> {
         let token = token.as_ref().trim();
         Ok(if let Some((val, suf)) = token.rsplit_once('_') {
             match suf {
@@ -32,33 +51,35 @@ impl Value {
         })
     }
 
-    pub fn into_float(self) -> f64 {
+    // `Null` is never a number: it is a missing operand or a value that was never pushed
+    pub fn into_float(self) -> Result<f64, Panic> {
         use Value::*;
-        match self {
+        Ok(match self {
             Float(v) => v,
             Int(v) => v as f64,
             Uint(v) => v as f64,
-            Null => panic!(),
-        }
+            Null => return Err(Panic::InvalidOperandValue(self)),
+        })
     }
 
-    pub fn into_int(self) -> isize {
+    pub fn into_int(self) -> Result<isize, Panic> {
         use Value::*;
-        match self {
+        Ok(match self {
             Float(v) => v as isize,
             Int(v) => v,
             Uint(v) => v as isize,
-            Null => panic!(),
-        }
+            Null => return Err(Panic::InvalidOperandValue(self)),
+        })
     }
-    pub fn into_uint(self) -> usize {
+
+    pub fn into_uint(self) -> Result<usize, Panic> {
         use Value::*;
-        match self {
+        Ok(match self {
             Float(v) => v.abs() as usize,
             Int(v) => v.unsigned_abs(),
             Uint(v) => v,
-            Null => panic!(),
-        }
+            Null => return Err(Panic::InvalidOperandValue(self)),
+        })
     }
 
     pub fn is_null(&self) -> bool {
@@ -69,14 +90,14 @@ impl Value {
         false
     }
 
-    pub fn into_type_of(self, other: Value) -> Self {
+    pub fn into_type_of(self, other: Value) -> Result<Self, Panic> {
         use Value::*;
-        match other {
-            Float(_) => Float(self.into_float()),
-            Int(_) => Int(self.into_int()),
-            Uint(_) => Uint(self.into_uint()),
-            Null => Null,
-        }
+        Ok(match other {
+            Float(_) => Float(self.into_float()?),
+            Int(_) => Int(self.into_int()?),
+            Uint(_) => Uint(self.into_uint()?),
+            Null => return Err(Panic::InvalidOperandValue(other)),
+        })
     }
 }
 
@@ -100,97 +121,478 @@ pub enum InstructionKind {
     Call = 13,
     Halt = 14,
     Swap = 15,
+    Bounds = 16,
+    Stats = 17,
+    Frame = 18,
+    Local = 19,
+    LocalStore = 20,
+    Leave = 21,
+    Gosub = 22,
+    Retsub = 23,
+    Exchange = 24,
+    Over = 25,
+    Rot = 26,
+    RotBack = 27,
+    Mod = 28,
+    And = 29,
+    Or = 30,
+    Xor = 31,
+    Not = 32,
+    Shl = 33,
+    Shr = 34,
+    LogicalNot = 35,
+    Lt = 36,
+    Gt = 37,
+    Le = 38,
+    Ge = 39,
+    JumpZero = 40,
+    JumpNonZero = 41,
+    Neg = 42,
+    Roll = 43,
+    Abs = 44,
+    Floor = 45,
+    Ceil = 46,
+    Round = 47,
+    Print = 48,
+    PrintChar = 49,
+    Depth = 50,
+    Read = 51,
+    ReadChar = 52,
+    DropAt = 53,
+    ToFloat = 54,
+    ToUint = 55,
+    ToInt = 56,
+    PrintNoLn = 57,
+    Sqrt = 58,
+    Pow = 59,
+    Rand = 60,
+    Tick = 61,
+    Store = 62,
+    Load = 63,
+    JumpRel = 64,
+    ToReg = 65,
+    FromReg = 66,
+    TypeOf = 67,
+    Cast = 68,
+    PrintString = 69,
+    Cmp = 70,
+    Syscall = 71,
+    PopCount = 72,
+    Clz = 73,
+    Ctz = 74,
+    Assert = 75,
+    RotL = 76,
+    RotR = 77,
+    ReadTry = 78,
 }
 
-impl InstructionKind {
-    fn try_parse<T: AsRef<str>>(src: T) -> Result<Self, ()> {
-        use InstructionKind::*;
+// What an instruction needs from the outside world, every instruction has to declare one
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Category {
+    Pure,
+    Io,
+    Natives,
+    Memory,
+    Nondeterminism,
+}
+
+impl Category {
+    pub fn try_parse<T: AsRef<str>>(src: T) -> Result<Self, ()> {
+        use Category::*;
         Ok(match src.as_ref() {
-            "неоп" => Nop,
-            "кинь" => Drop,
-            "копію" => Dup,
-            "клади" => Push,
-            "крок" => Jump,
-            "рівн" => Eq,
-            "різн" => Sub,
-            "множ" => Mul,
-            "діли" => Div,
-            "сума" => Sum,
-            "нерівн" => NotEq,
-            "ззовні" => Extern,
-            "вертай" => Return,
-            "клич" => Call,
-            "кінчай" => Halt,
-            "міняй" => Swap,
+            "io" => Io,
+            "natives" => Natives,
+            "memory" => Memory,
+            "nondeterminism" => Nondeterminism,
             _ => return Err(()),
         })
     }
+}
 
-    fn try_from_idx(idx: u8) -> Self {
-        use InstructionKind::*;
-        match idx {
-            0 => Nop,
-            1 => Push,
-            2 => Dup,
-            3 => Drop,
-            4 => Eq,
-            5 => Jump,
-            6 => Sum,
-            7 => Sub,
-            8 => Mul,
-            9 => Div,
-            10 => NotEq,
-            11 => Extern,
-            12 => Return,
-            13 => Call,
-            14 => Halt,
-            15 => Swap,
-            _ => panic!(),
-        }
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Operand {
+    None,
+    Required,
+    // Taken from the stack when it is missing
+    Optional,
+}
+
+pub struct InstructionInfo {
+    pub kind: InstructionKind,
+    pub mnemonic: &'static str,
+    pub alias: &'static str,
+    pub operand: Operand,
+    pub category: Category,
+    pub stack_effect: &'static str,
+    pub description_ua: &'static str,
+    pub description_en: &'static str,
+}
+
+macro_rules! instructions {
+    ($($kind:ident $mnemonic:literal $alias:literal $operand:ident $category:ident $effect:literal $ua:literal $en:literal)*) => {
+        pub const INSTRUCTIONS: &[InstructionInfo] = &[$(InstructionInfo {
+            kind: InstructionKind::$kind,
+            mnemonic: $mnemonic,
+            alias: $alias,
+            operand: Operand::$operand,
+            category: Category::$category,
+            stack_effect: $effect,
+            description_ua: $ua,
+            description_en: $en,
+        }),*];
+    };
+}
+
+// The single source of truth about instructions, indexed by opcode
+instructions! {
+    Nop "неоп" "nop" None Pure "( -- )"
+        "нічого не робити" "do nothing"
+    Push "клади" "push" Required Pure "( -- a )"
+        "покласти операнд на стек" "push the operand on the stack"
+    Dup "копію" "dup" Optional Pure "( x ..n.. [n] -- x ..n.. x )"
+        "скопіювати значення з глибини операнда або з верхівки стеку"
+        "copy the value at the depth from the operand or from the top of the stack"
+    Drop "кинь" "drop" None Pure "( a -- )"
+        "викинути верхнє значення" "drop the top value"
+    Eq "рівн" "eq" None Pure "( b a -- b a b==a )"
+        "покласти 1 якщо два верхні значення рівні, інакше 0" "push 1 if the two top values are equal, otherwise 0"
+    Jump "крок" "jmp" Required Pure "( -- )"
+        "перейти до адреси операнда" "jump to the operand address"
+    Sum "сума" "add" None Pure "( b a -- b+a )"
+        "додати два верхні значення" "add the two top values"
+    Sub "різн" "sub" None Pure "( b a -- b-a )"
+        "відняти верхнє значення від наступного" "subtract the top value from the next one"
+    Mul "множ" "mul" None Pure "( b a -- b*a )"
+        "помножити два верхні значення" "multiply the two top values"
+    Div "діли" "div" None Pure "( b a -- b/a )"
+        "поділити наступне значення на верхнє" "divide the next value by the top one"
+    NotEq "нерівн" "neq" None Pure "( b a -- b a b!=a )"
+        "покласти 1 якщо два верхні значення різні, інакше 0" "push 1 if the two top values differ, otherwise 0"
+    Extern "ззовні" "extern" Required Io "( -- )"
+        "викликати зовнішню функцію за номером операнда" "call the external function selected by the operand"
+    Return "вертай" "ret" None Pure "( addr -- )"
+        "перейти до адреси з верхівки стеку" "jump to the address on top of the stack"
+    Call "клич" "call" Required Pure "( -- addr )"
+        "покласти адресу повернення та перейти до адреси операнда" "push the return address and jump to the operand address"
+    Halt "кінчай" "halt" None Pure "( -- )"
+        "завершити програму" "terminate the program"
+    Swap "міняй" "swap" Required Pure "( x ..n.. a -- a ..n.. x )"
+        "поміняти верхнє значення зі значенням на глибині операнда" "swap the top value with the value at the operand depth"
+    Bounds "межа" "bounds" Required Pure "( i -- i )"
+        "перевірити, що верхнє значення є індексом менше операнда" "check that the top value is an index below the operand"
+    Stats "статистика" "stats" Required Pure "( -- n )"
+        "покласти лічильник інструкцій (0), найбільшу глибину стеку (1) або залишок газу (2)"
+        "push the instruction counter (0), the stack high-water mark (1) or the gas left (2)"
+    Frame "кадр" "frame" Required Pure "( -- )"
+        "відкрити кадр з кількістю локальних значень з операнда" "open a frame with the operand number of local values"
+    Local "локал" "local" Required Pure "( -- x )"
+        "покласти локальне значення за номером операнда" "push the local value with the operand index"
+    LocalStore "локал_зберіг" "local_store" Required Pure "( x -- )"
+        "зберегти верхнє значення у локальне за номером операнда" "store the top value into the local with the operand index"
+    Leave "назад" "leave" None Pure "( -- )"
+        "закрити поточний кадр" "close the current frame"
+    Gosub "виклик" "gosub" Required Pure "( -- )"
+        "запам'ятати адресу повернення у стеку повернень та перейти до адреси операнда"
+        "save the return address on the return stack and jump to the operand address"
+    Retsub "повернись" "retsub" None Pure "( -- )"
+        "перейти до останньої адреси зі стеку повернень" "jump to the last address from the return stack"
+    Exchange "обмін" "xchg" None Pure "( b a -- a b )"
+        "поміняти місцями два верхні значення" "exchange the two top values"
+    Over "понад" "over" None Pure "( b a -- b a b )"
+        "скопіювати друге зверху значення на верхівку" "copy the second value from the top onto the top"
+    Rot "оберт" "rot" None Pure "( a b c -- b c a )"
+        "перемістити третє зверху значення на верхівку" "move the third value from the top onto the top"
+    RotBack "оберт_назад" "-rot" None Pure "( a b c -- c a b )"
+        "перемістити верхнє значення на третє місце зверху" "move the top value to the third place from the top"
+    Mod "остача" "mod" None Pure "( b a -- b%a )"
+        "остача від ділення наступного значення на верхнє" "remainder of dividing the next value by the top one"
+    And "і" "and" None Pure "( b a -- b&a )"
+        "побітове І двох верхніх цілих значень" "bitwise AND of the two top integer values"
+    Or "або" "or" None Pure "( b a -- b|a )"
+        "побітове АБО двох верхніх цілих значень" "bitwise OR of the two top integer values"
+    Xor "абоне" "xor" None Pure "( b a -- b^a )"
+        "побітове виключне АБО двох верхніх цілих значень" "bitwise XOR of the two top integer values"
+    Not "ні" "not" None Pure "( a -- ~a )"
+        "інвертувати біти верхнього цілого значення" "invert the bits of the top integer value"
+    Shl "вліво" "shl" None Pure "( b a -- b<<a )"
+        "зсунути біти наступного цілого значення вліво на верхнє невід'ємне ціле значення" "shift the bits of the next integer value left by the top non-negative integer value"
+    Shr "вправо" "shr" None Pure "( b a -- b>>a )"
+        "зсунути біти наступного цілого значення вправо на верхнє невід'ємне ціле значення" "shift the bits of the next integer value right by the top non-negative integer value"
+    LogicalNot "не" "lnot" None Pure "( a -- a==0 )"
+        "замінити верхнє значення на 1, якщо воно нуль, інакше на 0" "replace the top value with 1 if it is zero, otherwise with 0"
+    Lt "менше" "lt" None Pure "( b a -- b<a )"
+        "1, якщо наступне значення менше за верхнє, інакше 0" "1 if the next value is less than the top one, otherwise 0"
+    Gt "більше" "gt" None Pure "( b a -- b>a )"
+        "1, якщо наступне значення більше за верхнє, інакше 0" "1 if the next value is greater than the top one, otherwise 0"
+    Le "мменше" "le" None Pure "( b a -- b<=a )"
+        "1, якщо наступне значення не більше за верхнє, інакше 0" "1 if the next value is less than or equal to the top one, otherwise 0"
+    Ge "мбільше" "ge" None Pure "( b a -- b>=a )"
+        "1, якщо наступне значення не менше за верхнє, інакше 0" "1 if the next value is greater than or equal to the top one, otherwise 0"
+    JumpZero "крок_нуль" "jz" Required Pure "( a -- )"
+        "перейти до адреси операнда, якщо верхнє значення нуль" "jump to the operand address if the top value is zero"
+    JumpNonZero "крок_ненуль" "jnz" Required Pure "( a -- )"
+        "перейти до адреси операнда, якщо верхнє значення не нуль" "jump to the operand address if the top value is not zero"
+    Neg "відємн" "neg" None Pure "( a -- -a )"
+        "змінити знак верхнього значення" "negate the top value"
+    Roll "ролл" "roll" Required Pure "( x .. a -- .. a x )"
+        "перемістити значення з глибини операнда на верхівку" "move the value at the operand depth onto the top"
+    Abs "абсол" "abs" None Pure "( a -- |a| )"
+        "замінити верхнє значення на його модуль" "replace the top value with its absolute value"
+    Floor "підлога" "floor" None Pure "( a -- ⌊a⌋ )"
+        "округлити верхнє дробове значення вниз" "round the top float value down"
+    Ceil "стеля" "ceil" None Pure "( a -- ⌈a⌉ )"
+        "округлити верхнє дробове значення вгору" "round the top float value up"
+    Round "округл" "round" None Pure "( a -- [a] )"
+        "округлити верхнє дробове значення до найближчого цілого, половини від нуля"
+        "round the top float value to the nearest whole number, halves away from zero"
+    Print "друк" "print" None Io "( a -- )"
+        "зняти верхнє значення та вивести його окремим рядком" "pop the top value and print it on its own line"
+    PrintChar "друкз" "printc" None Io "( a -- )"
+        "зняти верхнє значення та вивести символ Unicode з цим кодом" "pop the top value and print the Unicode character with that code"
+    Depth "глибина" "depth" None Pure "( -- n )"
+        "покласти кількість значень у стеку" "push the number of values on the stack"
+    Read "читай" "read" Optional Io "( [адр n] -- a )"
+        "прочитати число з вводу до пробілу та покласти його, з операндом 1 спершу вивести рядок підказки з пам'яті у термінал"
+        "read a whitespace-delimited number from the input and push it, with the operand 1 first print the prompt string from the memory to a terminal"
+    ReadChar "читайз" "readc" None Io "( -- c )"
+        "прочитати символ з вводу та покласти його код, -1 в кінці вводу"
+        "read a character from the input and push its code, -1 at the end of the input"
+    DropAt "кинь_де" "dropat" Required Pure "( x ..n.. -- ..n.. )"
+        "викинути значення з глибини операнда" "drop the value at the operand depth"
+    ToFloat "до_дроб" "tofloat" None Pure "( a -- a_дроб )"
+        "перетворити верхнє значення на дробове" "convert the top value to a float"
+    ToUint "до_ціл" "touint" None Pure "( a -- a_ціл )"
+        "перетворити верхнє невід'ємне значення на беззнакове ціле" "convert the top non-negative value to an unsigned integer"
+    ToInt "до_зціл" "toint" None Pure "( a -- a_зціл )"
+        "перетворити верхнє значення на знакове ціле" "convert the top value to a signed integer"
+    PrintNoLn "друкн" "printn" None Io "( a -- )"
+        "зняти верхнє значення та вивести його без нового рядка" "pop the top value and print it without a new line"
+    Sqrt "корінь" "sqrt" None Pure "( a -- √a )"
+        "замінити верхнє невід'ємне значення на його дробовий квадратний корінь"
+        "replace the top non-negative value with its float square root"
+    Pow "степінь" "pow" None Pure "( b a -- b^a )"
+        "піднести наступне значення до степеня верхнього, результат має тип основи"
+        "raise the next value to the power of the top one, the result has the type of the base"
+    Rand "випадк" "rand" None Nondeterminism "( -- r )"
+        "покласти наступне псевдовипадкове беззнакове ціле" "push the next pseudo-random unsigned integer"
+    Tick "тік" "tick" None Nondeterminism "( -- t )"
+        "покласти кількість наносекунд від створення машини" "push the number of nanoseconds since the machine was created"
+    Store "збережи" "store" None Memory "( v addr -- )"
+        "записати наступне значення до комірки пам'яті за верхньою адресою" "write the next value into the memory cell at the top address"
+    Load "завантаж" "load" None Memory "( addr -- v )"
+        "замінити адресу на верхівці значенням з комірки пам'яті" "replace the address on the top with the value of the memory cell"
+    JumpRel "крок_від" "jmprel" Required Pure "( -- )"
+        "перейти на кількість інструкцій з операнда від поточної" "jump by the operand number of instructions from the current one"
+    ToReg "у_рег" "toreg" Required Pure "( a -- )"
+        "зняти верхнє значення до регістра за номером операнда" "pop the top value into the register with the operand index"
+    FromReg "з_рег" "fromreg" Required Pure "( -- a )"
+        "покласти значення регістра за номером операнда" "push the value of the register with the operand index"
+    TypeOf "тип" "typeof" None Pure "( a -- a t )"
+        "покласти тип верхнього значення: 1 _зціл (ТИП_ЗЦІЛ), 2 _ціл (ТИП_ЦІЛ), 3 _дроб (ТИП_ДРОБ)" "push the type of the top value: 1 _зціл (ТИП_ЗЦІЛ), 2 _ціл (ТИП_ЦІЛ), 3 _дроб (ТИП_ДРОБ)"
+    Cast "привести" "cast" Required Pure "( a -- a' )"
+        "перетворити верхнє значення на тип з кодом операнда: 0 _зціл, 1 _ціл, 2 _дроб" "convert the top value to the type with the operand code: 0 _зціл, 1 _ціл, 2 _дроб"
+    PrintString "друкр" "prints" None Io "( адр n -- )"
+        "зняти довжину та адресу і вивести n символів з пам'яті" "pop a length and an address and print n characters from the memory"
+    Cmp "пор" "cmp" None Pure "( b a -- r )"
+        "зняти два значення та покласти -1, якщо b < a, 0, якщо b = a, або 1, якщо b > a" "pop two values and push -1 if b < a, 0 if b = a or 1 if b > a"
+    Syscall "сис" "syscall" Required Natives "( .. -- .. )"
+        "системний виклик з номером операнда, 1 - запис n байтів з пам'яті до fd: ( fd адр n -- k ), 2 (файл_читати) - читання файлу до пам'яті: ( шлях n адр -- k ), 3 (файл_писати) - запис k байтів з пам'яті до файлу: ( шлях n адр k -- k )"
+        "system call with the operand number, 1 - write n bytes from the memory to the fd: ( fd адр n -- k ), 2 (файл_читати) - read a file into the memory: ( шлях n адр -- k ), 3 (файл_писати) - write k bytes from the memory to a file: ( шлях n адр k -- k )"
+    PopCount "кількість_бітів" "popcnt" None Pure "( a -- n )"
+        "замінити ціле значення кількістю одиничних бітів" "replace the integer value with the number of its set bits"
+    Clz "провідні_нулі" "clz" None Pure "( a -- n )"
+        "замінити ціле значення кількістю старших нульових бітів" "replace the integer value with the number of its leading zero bits"
+    Ctz "хвостові_нулі" "ctz" None Pure "( a -- n )"
+        "замінити ціле значення кількістю молодших нульових бітів" "replace the integer value with the number of its trailing zero bits"
+    Assert "перевір" "assert" None Pure "( a -- )"
+        "зняти верхнє значення та зупинити програму з помилкою, якщо воно 0" "pop the top value and stop the program with an error if it is 0"
+    RotL "поворот_л" "rotl" None Pure "( b a -- b<<<a )"
+        "повернути біти наступного цілого значення вліво на верхнє невід'ємне ціле значення" "rotate the bits of the next integer value left by the top non-negative integer value"
+    RotR "поворот_п" "rotr" None Pure "( b a -- b>>>a )"
+        "повернути біти наступного цілого значення вправо на верхнє невід'ємне ціле значення" "rotate the bits of the next integer value right by the top non-negative integer value"
+    ReadTry "читай_спроба" "tryread" Optional Io "( [адр n] -- a 1 | 0 )"
+        "як \"читай\", але покласти 1 після числа або лише 0 в кінці вводу"
+        "like \"читай\", but push 1 after the number or only 0 at the end of the input"
+}
+
+impl InstructionKind {
+    pub fn info(&self) -> &'static InstructionInfo {
+        &INSTRUCTIONS[*self as usize]
+    }
+
+    fn try_parse<T: AsRef<str>>(src: T) -> Result<Self, ()> {
+        INSTRUCTIONS
+            .iter()
+            .find(|i| i.mnemonic == src.as_ref())
+            .map(|i| i.kind)
+            .ok_or(())
+    }
+
+    fn try_from_idx(idx: u8) -> Option<Self> {
+        INSTRUCTIONS.get(idx as usize).map(|i| i.kind)
     }
 
     fn has_operand(&self) -> bool {
+        self.info().operand != Operand::None
+    }
+
+    // Jumps stay inside the routine, unlike calls
+    pub fn is_jump(&self) -> bool {
+        use InstructionKind::*;
+        matches!(self, Jump | JumpZero | JumpNonZero | JumpRel)
+    }
+
+    // The operand is an address in the program
+    pub fn is_branch(&self) -> bool {
         use InstructionKind::*;
-        matches!(self, Push | Dup | Jump | Call | Swap | Extern)
+        self.is_jump() || matches!(self, Call | Gosub)
     }
 }
 
+// Every instruction has to be described in the table exactly once, at its opcode
+const _: () = {
+    let mut i = 0;
+    while i < INSTRUCTIONS.len() {
+        assert!(INSTRUCTIONS[i].kind as usize == i);
+        i += 1;
+    }
+    assert!(InstructionKind::ReadTry as usize + 1 == INSTRUCTIONS.len());
+    assert!(INSTRUCTIONS.len() <= DATA_CHUNK as usize);
+};
+
 #[derive(Copy, Clone, Debug, Default)]
 pub struct Instruction {
     pub kind: InstructionKind,
     pub operand: Value,
+    /// Set by the `?` suffix. The top of the stack is always popped before
+    /// the instruction, whether it is executed or skipped: any non-zero value
+    /// (`0.5_дроб` and `-1` included) executes it, zero skips it and `Null`
+    /// or an empty stack is a `StackUnderflow`.
     pub conditional: bool,
 }
 
-pub fn deserialize(se: SerializedInst) -> Instruction {
-    let kind = InstructionKind::try_from_idx(se[0]);
+impl Instruction {
+    // Address a branch at `addr` goes to, `None` when its operand is missing or leads before the program
+    pub fn target(&self, addr: usize) -> Option<usize> {
+        match self.kind {
+            InstructionKind::JumpRel => addr.checked_add_signed(self.operand.into_int().ok()?),
+            _ => self.operand.into_uint().ok(),
+        }
+    }
+
+    // Points the branch at `addr` to the `target`
+    pub fn retarget(&mut self, addr: usize, target: usize) {
+        self.operand = match self.kind {
+            InstructionKind::JumpRel => Value::Int(target as isize - addr as isize),
+            _ => Value::Uint(target),
+        };
+    }
+}
+
+pub fn deserialize(se: SerializedInst) -> Result<Instruction, Panic> {
+    let kind = InstructionKind::try_from_idx(se[0]).ok_or(Panic::CorruptedBytecode(format!(
+        "невідомий код інструкції {code}",
+        code = se[0]
+    )))?;
     let inst_opts = se[1];
-    let operand_chunck = &se[2..INST_CHUNCK_SIZE];
-    let chunck = operand_chunck.try_into().unwrap();
-    let (n, operand) = match inst_opts {
-        200.. => (200, Value::Float(f64::from_le_bytes(chunck))),
-        100.. => (100, Value::Uint(usize::from_le_bytes(chunck))),
-        10.. => (10, Value::Int(isize::from_le_bytes(chunck))),
-        _ => (10, Value::Null),
+    let chunck = se[2..INST_CHUNCK_SIZE].try_into().unwrap();
+    let operand = match (inst_opts - inst_opts % 10, inst_opts % 10) {
+        (200, 0 | 1) => Value::Float(f64::from_le_bytes(chunck)),
+        (100, 0 | 1) => Value::Uint(usize::from_le_bytes(chunck)),
+        (10, 0 | 1) => Value::Int(isize::from_le_bytes(chunck)),
+        (0, 0 | 1) => Value::Null,
+        _ => {
+            return Err(Panic::CorruptedBytecode(format!(
+                "неможливе значення опцій інструкції {inst_opts}"
+            )))
+        }
     };
 
-    Instruction {
+    Ok(Instruction {
         kind,
         operand,
-        conditional: inst_opts % n != 0,
-    }
+        conditional: inst_opts % 10 == 1,
+    })
+}
+
+// Decodes the instruction from the beginning of `bytes`, returning it
+// together with the number of bytes it took
+pub fn disassemble_chunk(bytes: &[u8]) -> Result<(Instruction, usize), Panic> {
+    let chunck = bytes
+        .get(..INST_CHUNCK_SIZE)
+        .ok_or(Panic::CorruptedBytecode(format!(
+            "неповна інструкція розміром {size} байт",
+            size = bytes.len()
+        )))?;
+
+    Ok((deserialize(chunck.try_into().unwrap())?, INST_CHUNCK_SIZE))
+}
+
+// Decodes every instruction in `bytes`. A corrupted instruction yields an error
+// and decoding goes on from the next chunk boundary.
+pub fn disassemble_all(bytes: &[u8]) -> impl Iterator<Item = Result<Instruction, Panic>> + '_ {
+    bytes
+        .chunks(INST_CHUNCK_SIZE)
+        .map(|chunck| disassemble_chunk(chunck).map(|(inst, _)| inst))
+}
+
+// Splits the bytecode into the instructions and the data after them
+pub fn split_data(bytes: &[u8]) -> (&[u8], &[u8]) {
+    let code = bytes
+        .chunks(INST_CHUNCK_SIZE)
+        .position(|chunck| chunck[0] == DATA_CHUNK)
+        .map_or(bytes.len(), |n| n * INST_CHUNCK_SIZE);
+
+    bytes.split_at(code)
+}
+
+// Decodes the values of the data chunks, every chunk in `bytes` has to be one of them
+pub fn disassemble_data(bytes: &[u8]) -> impl Iterator<Item = Result<Value, Panic>> + '_ {
+    bytes.chunks(INST_CHUNCK_SIZE).map(|chunck| {
+        let mut se = SerializedInst::try_from(chunck).map_err(|_| {
+            Panic::CorruptedBytecode(format!(
+                "неповні дані розміром {size} байт",
+                size = chunck.len()
+            ))
+        })?;
+        if se[0] != DATA_CHUNK {
+            return Err(Panic::CorruptedBytecode(format!(
+                "інструкція з кодом {code} після даних",
+                code = se[0]
+            )));
+        }
+        se[0] = InstructionKind::Nop as u8;
+        match deserialize(se)? {
+            Instruction {
+                operand,
+                conditional: false,
+                ..
+            } if !operand.is_null() => Ok(operand),
+            _ => Err(Panic::CorruptedBytecode(format!(
+                "неможливе значення опцій даних {opts}",
+                opts = se[1]
+            ))),
+        }
+    })
 }
 
 // Serialized instruction contains 10 bytes:
 // 		1 - kind of instruction
-// 		2 - information about instruction and it's operand
-// 			1/0 -conditional/not
-// 			i < 10 - operand is Value::Null
-// 			i >= 10 - operand is i64
-// 			i >= 100 - operand is u64
-// 			i >= 200 - operand is f64
+// 		2 - information about instruction and it's operand, sum of:
+// 			0/1 - not conditional/conditional
+// 			0 - operand is Value::Null
+// 			10 - operand is i64
+// 			100 - operand is u64
+// 			200 - operand is f64
+// 		    so the only valid values are 0, 1, 10, 11, 100, 101, 200 and 201
 //
 // 		3..=10 - bytes representation of the value
+//
+// Bytecode is just a sequence of these chunks without any header, so no
+// timestamp or other run dependent data ends up in it and the same USM
+// always translates to the same bytes. The values of the `дані` directives
+// follow the instructions as chunks of the kind `DATA_CHUNK` with the value
+// encoded like an operand.
 
 pub fn serialize(inst: Instruction) -> SerializedInst {
     let mut se = [0; INST_CHUNCK_SIZE];
@@ -220,32 +622,329 @@ pub fn serialize(inst: Instruction) -> SerializedInst {
     se
 }
 
-enum Token {
+/// Single unit of the USM source produced by `parse`.
+/// More kinds of tokens (strings, directives, annotations) are expected,
+/// so any `match` on it outside of this module has to keep a wildcard arm.
+#[non_exhaustive]
+enum Token<'a> {
     Value(Value),
     Inst(Instruction),
-    LabelExpand(String),
+    LabelExpand(&'a str),
+    // Operand holding an address of the code, like the table base of `таблиця`
+    CodeAddr(usize),
+}
+
+#[derive(Debug, Default)]
+pub struct LabelTable {
+    addrs: HashMap<Box<str>, usize>,
+    // Definition order, anything walking over the labels must not depend on the hash map order
+    order: Vec<Box<str>>,
+}
+
+impl LabelTable {
+    // The first definition of a label wins, later ones are ignored
+    pub fn define(&mut self, name: &str, addr: usize) {
+        if !self.addrs.contains_key(name) {
+            self.addrs.insert(name.into(), addr);
+            self.order.push(name.into());
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<usize> {
+        self.addrs.get(name).copied()
+    }
+}
+
+// `таблиця мітка0 мітка1 ..` pops an index and jumps to the label with that index:
+//         межа N          ;; the index has to be in 0..N
+//         клади 2_ціл
+//         множ
+//         клади T_ціл
+//         сума
+//         вертай          ;; jump to T + 2 * index
+//     T:  клади мітка0
+//         вертай
+//         клади мітка1
+//         вертай
+//         ..
+fn expand_jump_table<'a>(targets: &[&'a str], addr: usize) -> Vec<Token<'a>> {
+    let inst = |kind| {
+        Token::Inst(Instruction {
+            kind,
+            operand: Value::Null,
+            conditional: false,
+        })
+    };
+
+    use InstructionKind::*;
+    let mut tokens = vec![
+        inst(Bounds),
+        Token::Value(Value::Uint(targets.len())),
+        inst(Push),
+        Token::Value(Value::Uint(2)),
+        inst(Mul),
+        inst(Push),
+        Token::CodeAddr(addr + 6),
+        inst(Sum),
+        inst(Return),
+    ];
+    for target in targets {
+        tokens.extend([inst(Push), Token::LabelExpand(target), inst(Return)]);
+    }
+
+    tokens
+}
+
+#[derive(Debug, Default)]
+pub struct ParseOptions {
+    pub strict_types: bool,
+    pub aliases: HashMap<String, InstructionKind>,
 }
 
-fn parse(source: String) -> (Vec<Token>, Vec<(String, usize)>) {
-    let mut tokens = Vec::<Token>::new();
-    let mut labels = Vec::<(String, usize)>::new();
+// Every line of the aliases file is `псевдонім=канонічна`, empty lines and comments are skipped
+pub fn parse_aliases<T: AsRef<str>>(src: T) -> Result<HashMap<String, InstructionKind>, Panic> {
+    let mut aliases = HashMap::<String, InstructionKind>::new();
+    for (n, line) in src.as_ref().lines().enumerate() {
+        let line = line
+            .split_once(COMMENT_TOKEN)
+            .map(|(l, _)| l)
+            .unwrap_or(line)
+            .trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let err = |msg: String| Panic::ParseError {
+            message: format!("файл псевдонімів: {msg}"),
+            line: n + 1,
+            col: 1,
+        };
+        let (alias, canonical) = line
+            .split_once('=')
+            .map(|(a, c)| (a.trim(), c.trim()))
+            .ok_or_else(|| {
+                err(format!(
+                    "очікувався вираз \"псевдонім=канонічна\", а не \"{line}\""
+                ))
+            })?;
+        let kind = InstructionKind::try_parse(canonical)
+            .map_err(|_| err(format!("невідома інструкція \"{canonical}\"")))?;
+        if alias.is_empty()
+            || alias.contains(char::is_whitespace)
+            || alias.ends_with([':', '?'])
+            || Value::try_parse(alias).is_ok()
+        {
+            return Err(err(format!("неможливий псевдонім \"{alias}\"")));
+        }
+        if InstructionKind::try_parse(alias).is_ok()
+            || [JUMP_TABLE_TOKEN, DATA_DIRECTIVE].contains(&alias)
+        {
+            return Err(err(format!(
+                "псевдонім \"{alias}\" збігається з інструкцією"
+            )));
+        }
+        if aliases.insert(alias.into(), kind).is_some() {
+            return Err(err(format!("псевдонім \"{alias}\" вже визначено")));
+        }
+    }
+
+    Ok(aliases)
+}
+
+// Line and column of a token in the USM source, both counted from 1
+#[derive(Copy, Clone, Debug, Default)]
+struct SourcePos {
+    line: usize,
+    col: usize,
+}
+
+fn error_at(pos: SourcePos, message: String) -> Panic {
+    Panic::ParseError {
+        message,
+        line: pos.line,
+        col: pos.col,
+    }
+}
+
+// What the assembler knows about an instruction beyond its bytes, the optimizer
+// carries it along with every instruction it moves
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SourceInfo {
+    pos: SourcePos,
+    // The operand is an address of the code that the instruction itself does not jump to,
+    // e.g. `клади мітка` for a later `вертай`, so it has to move together with the code
+    pub code_operand: bool,
+}
+
+impl SourceInfo {
+    pub fn error(&self, message: String) -> Panic {
+        error_at(self.pos, message)
+    }
+
+    pub fn line(&self) -> usize {
+        self.pos.line
+    }
+}
+
+type Tokens<'a> = Vec<(Token<'a>, SourcePos)>;
+
+// Values of all `дані` directives in the order of the source, they are placed into
+// the memory from the address 0 before the run. Every name holds the address of its first value.
+#[derive(Debug, Default)]
+struct DataSection {
+    values: Vec<Value>,
+    labels: LabelTable,
+}
+
+// Values of a `дані` directive, numbers and string literals. Every character of a string
+// becomes its `_ціл` code, the escapes are \n, \t, \" and \\
+fn parse_data_values(mut rest: &str, name: &str, strict_types: bool) -> Result<Vec<Value>, String> {
+    let mut values = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        if let Some(string) = rest.strip_prefix('"') {
+            let mut chars = string.char_indices();
+            rest = loop {
+                let c = match chars.next() {
+                    Some((end, '"')) => break &string[end + 1..],
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, 'n')) => '\n',
+                        Some((_, 't')) => '\t',
+                        Some((_, c @ ('"' | '\\'))) => c,
+                        Some((_, c)) => {
+                            return Err(format!(
+                                "невідома послідовність \"\\{c}\" у даних \"{name}\""
+                            ))
+                        }
+                        None => return Err(format!("незакритий рядок у даних \"{name}\"")),
+                    },
+                    Some((_, c)) => c,
+                    None => return Err(format!("незакритий рядок у даних \"{name}\"")),
+                };
+                values.push(Value::Uint(c as usize));
+            };
+        } else if let Some(word) = rest.split_whitespace().next() {
+            let val = Value::try_parse(word)
+                .map_err(|_| format!("\"{word}\" не є значенням для даних \"{name}\""))?;
+            if strict_types && !word.contains('_') {
+                return Err(format!(
+                    "значення \"{word}\" без суфіксу типу, можливо \"{val}\""
+                ));
+            }
+            values.push(val);
+            rest = &rest[word.len()..];
+        } else {
+            return Ok(values);
+        }
+    }
+}
+
+fn predefined(name: &str) -> Option<Value> {
+    PREDEFINED_NAMES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, val)| *val)
+}
+
+fn parse<'a>(
+    source: &'a str,
+    opts: &ParseOptions,
+) -> Result<(Tokens<'a>, LabelTable, DataSection), Panic> {
+    let mut strict_types = opts.strict_types;
+    let kind_of = |word: &str| {
+        InstructionKind::try_parse(word).or_else(|_| opts.aliases.get(word).copied().ok_or(()))
+    };
+    let mut tokens = Tokens::new();
+    let mut labels = LabelTable::default();
+    let mut data = DataSection::default();
     let mut inst_count = 0;
 
-    for line in source
-        .lines()
-        .filter(|line| !line.trim_start().starts_with(COMMENT_TOKEN))
-    {
-        let line = line.split_once(COMMENT_TOKEN).map(|(l, _)| l).unwrap_or(line);
-        for word in line.split_whitespace() {
+    for (n, line) in source.lines().enumerate() {
+        if line.trim_start().starts_with(COMMENT_TOKEN) {
+            continue;
+        }
+        let line = line
+            .split_once(COMMENT_TOKEN)
+            .map(|(l, _)| l)
+            .unwrap_or(line);
+        let mut words = line.split_whitespace();
+        while let Some(word) = words.next() {
             let word = word.trim();
+            let offset = word.as_ptr() as usize - line.as_ptr() as usize;
+            let pos = SourcePos {
+                line: n + 1,
+                col: line[..offset].chars().count() + 1,
+            };
+
+            if word == STRICT_TYPES_DIRECTIVE {
+                strict_types = true;
+                continue;
+            }
+
+            if word == JUMP_TABLE_TOKEN {
+                let targets = words.by_ref().collect::<Vec<&str>>();
+                if targets.is_empty() {
+                    return Err(error_at(
+                        pos,
+                        format!("відсутні лейбли для \"{JUMP_TABLE_TOKEN}\""),
+                    ));
+                }
+                let expanded = expand_jump_table(&targets, inst_count);
+                inst_count += expanded
+                    .iter()
+                    .filter(|t| matches!(t, Token::Inst(_)))
+                    .count();
+                tokens.extend(expanded.into_iter().map(|t| (t, pos)));
+                continue;
+            }
+
+            if word == DATA_DIRECTIVE {
+                let (name, rest) = words
+                    .next()
+                    .and_then(|w| {
+                        let end = w.as_ptr() as usize - line.as_ptr() as usize + w.len();
+                        Some((w.strip_suffix(':')?, &line[end..]))
+                    })
+                    .filter(|(name, _)| !name.is_empty())
+                    .ok_or(error_at(
+                        pos,
+                        format!("очікувався вираз \"{DATA_DIRECTIVE} ім'я: значення ..\""),
+                    ))?;
+                if data.labels.get(name).is_some() {
+                    return Err(error_at(pos, format!("дані \"{name}\" вже визначено")));
+                }
+                data.labels.define(name, data.values.len());
+                let values = parse_data_values(rest, name, strict_types)
+                    .map_err(|message| error_at(pos, message))?;
+                if values.is_empty() {
+                    return Err(error_at(
+                        pos,
+                        format!("відсутні значення для даних \"{name}\""),
+                    ));
+                }
+                data.values.extend(values);
+                if data.values.len() > MEMORY_CAPACITY {
+                    return Err(error_at(
+                        pos,
+                        format!(
+                            "дані \"{name}\" не вміщаються у пам'ять розміром {MEMORY_CAPACITY}"
+                        ),
+                    ));
+                }
+                // The values take the rest of the line
+                break;
+            }
 
             if let Some(label) = word.strip_suffix(':') {
-                labels.push((label.into(), inst_count));
+                if predefined(label).is_some() {
+                    return Err(error_at(pos, format!("ім'я \"{label}\" зарезервовано")));
+                }
+                labels.define(label, inst_count);
                 continue;
             }
 
-            tokens.push(if let Some(inst) = word.strip_suffix('?') {
-                InstructionKind::try_parse(inst)
+            let token = if let Some(inst) = word.strip_suffix('?') {
+                kind_of(inst)
                     .map(|kind| {
                         inst_count += 1;
                         Token::Inst(Instruction {
@@ -254,10 +953,18 @@ fn parse(source: String) -> (Vec<Token>, Vec<(String, usize)>) {
                             conditional: true,
                         })
                     })
-                    .unwrap_or(Token::LabelExpand(word.into()))
+                    .unwrap_or(Token::LabelExpand(word))
             } else if let Ok(val) = Value::try_parse(word) {
+                if strict_types && !word.contains('_') {
+                    return Err(error_at(
+                        pos,
+                        format!("значення \"{word}\" без суфіксу типу, можливо \"{val}\""),
+                    ));
+                }
                 Token::Value(val)
-            } else if let Ok(kind) = InstructionKind::try_parse(word) {
+            } else if let Some(val) = predefined(word) {
+                Token::Value(val)
+            } else if let Ok(kind) = kind_of(word) {
                 inst_count += 1;
                 Token::Inst(Instruction {
                     kind,
@@ -265,75 +972,198 @@ fn parse(source: String) -> (Vec<Token>, Vec<(String, usize)>) {
                     conditional: false,
                 })
             } else {
-                Token::LabelExpand(word.into())
-            })
+                Token::LabelExpand(word)
+            };
+            tokens.push((token, pos));
         }
     }
 
-    (tokens, labels)
+    if let Some(name) = data
+        .labels
+        .order
+        .iter()
+        .find(|name| labels.get(name).is_some())
+    {
+        return Err(Panic::parse_error(format!(
+            "ім'я даних \"{name}\" збігається з лейблом"
+        )));
+    }
+
+    Ok((tokens, labels, data))
 }
 
-pub fn disassemble(src: String) -> Result<Array<Instruction, PROGRAM_INST_CAPACITY>, Panic> {
-    let mut program = Array::<Instruction, PROGRAM_INST_CAPACITY>::new();
-    let (src, labels_table) = parse(src);
+// Program, its labels, the values of the `дані` directives and the source info of every instruction
+pub type Assembled = (Vec<Instruction>, LabelTable, Vec<Value>, Vec<SourceInfo>);
+
+// Label names are borrowed from `src` while parsing, only the diagnostics and
+// the final label table keep their own copies.
+// The values of the `дані` directives and the source info of every instruction
+// are returned apart from the program.
+pub fn disassemble<T: AsRef<str>>(src: T, opts: &ParseOptions) -> Result<Assembled, Panic> {
+    let mut program = Vec::<Instruction>::new();
+    let mut source = Vec::<SourceInfo>::new();
+    let (src, labels_table, data) = parse(src.as_ref(), opts)?;
 
-    for token in src {
+    for (token, pos) in src {
         match token {
-            Token::Inst(inst) => program.push(inst),
+            Token::Inst(inst) => {
+                program.push(inst);
+                source.push(SourceInfo {
+                    pos,
+                    code_operand: false,
+                });
+            }
+            Token::CodeAddr(addr) => {
+                let last = program.last_mut().unwrap();
+                last.operand = Value::Uint(addr);
+                source.last_mut().unwrap().code_operand = true;
+            }
             Token::LabelExpand(name) => {
-                let last = program.get_last_mut();
-                if let InstructionKind::Nop = last.kind {
-                    return Err(Panic::ParseError(format!("не передбачений операнд у вигляді лейблу \"{name}\" для відсутьої інструкції")));
+                if program
+                    .last()
+                    .is_none_or(|i| i.kind == InstructionKind::Nop)
+                {
+                    return Err(error_at(pos, format!("не передбачений операнд у вигляді лейблу \"{name}\" для відсутьої інструкції")));
                 }
+                let addr = program.len() - 1;
+                let last = program.last_mut().unwrap();
                 if last.kind.has_operand() {
-                    last.operand = Value::Uint(
-                        labels_table
-                            .iter()
-                            .find(|l| l.0.contains(name.as_str()))
-                            .ok_or(Panic::ParseError(format!(
-                                "спроба використати неіснуючий лейбл \"{name}\" для інструкції \"{kind}\"",
-                                kind = last.kind
-                            )))?
-                            .1,
-                    );
+                    let (label, relative) = match name.strip_prefix(RELATIVE_LABEL_PREFIX) {
+                        Some(label) => (label, true),
+                        None => (name, false),
+                    };
+                    if relative && last.kind != InstructionKind::JumpRel {
+                        return Err(error_at(
+                            pos,
+                            format!(
+                                "відносний лейбл \"{name}\" можна використати лише для інструкції \"{kind}\"",
+                                kind = InstructionKind::JumpRel
+                            ),
+                        ));
+                    }
+                    let code_target = labels_table.get(label);
+                    source.last_mut().unwrap().code_operand =
+                        code_target.is_some() && !relative && !last.kind.is_branch();
+                    let target = code_target
+                        .or_else(|| {
+                            data.labels
+                                .get(label)
+                                .filter(|_| !relative && !last.kind.is_branch())
+                        })
+                        .ok_or(error_at(
+                        pos,
+                        format!(
+                            "спроба використати неіснуючий лейбл \"{label}\" для інструкції \"{kind}\"",
+                            kind = last.kind
+                        ),
+                    ))?;
+                    last.operand = if relative {
+                        Value::Int(target as isize - addr as isize)
+                    } else {
+                        Value::Uint(target)
+                    };
                 } else {
-                    return Err(Panic::ParseError(format!(
+                    return Err(error_at(pos, format!(
                         "спроба використати лейбл \"{name}\" як не передбачений операнд для інструкції \"{kind}\"",
                         kind = last.kind
                     )));
                 }
             }
             Token::Value(val) => {
-                let last = program.get_last_mut();
-                if let InstructionKind::Nop = last.kind {
-                    return Err(Panic::ParseError(format!(
-                        "не передбачений операнд \"{val}\" для відсутьої інструкції"
-                    )));
+                if program
+                    .last()
+                    .is_none_or(|i| i.kind == InstructionKind::Nop)
+                {
+                    return Err(error_at(
+                        pos,
+                        format!("не передбачений операнд \"{val}\" для відсутьої інструкції"),
+                    ));
                 }
+                let last = program.last_mut().unwrap();
                 if last.kind.has_operand() {
                     last.operand = val;
                 } else {
-                    return Err(Panic::ParseError(format!(
-                        "не передбачений операнд \"{val}\" для інструкції \"{kind}\"",
-                        kind = last.kind
-                    )));
+                    return Err(error_at(
+                        pos,
+                        format!(
+                            "не передбачений операнд \"{val}\" для інструкції \"{kind}\"",
+                            kind = last.kind
+                        ),
+                    ));
                 }
             }
         }
     }
 
-    if let Some(e) = program
-        .get_all()
+    if let Some((e, info)) = program
         .iter()
-        .find(|i| i.kind.has_operand() && i.operand.is_null())
+        .zip(&source)
+        .find(|(i, _)| i.kind.info().operand == Operand::Required && i.operand.is_null())
     {
-        return Err(Panic::ParseError(format!(
+        return Err(info.error(format!(
             "відсутнє значення для інструкції \"{kind}\"",
             kind = e.kind
         )));
     }
 
-    Ok(program)
+    Ok((program, labels_table, data.values, source))
+}
+
+pub fn serialize_data(val: Value) -> SerializedInst {
+    let mut se = serialize(Instruction {
+        kind: InstructionKind::Nop,
+        operand: val,
+        conditional: false,
+    });
+    se[0] = DATA_CHUNK;
+
+    se
+}
+
+// Characters that are kept as a string literal in the assembled data
+fn data_char(val: Value) -> Option<char> {
+    let Value::Uint(code) = val else {
+        return None;
+    };
+    u32::try_from(code)
+        .ok()
+        .and_then(char::from_u32)
+        .filter(|c| !c.is_control() || matches!(c, '\n' | '\t'))
+}
+
+// All the data goes into a single `дані` directive, the assembled instructions
+// have no labels and address it by numbers. Runs of at least two character codes
+// become string literals.
+pub fn assemble_data(data: &[Value]) -> String {
+    if data.is_empty() {
+        return String::new();
+    }
+
+    let mut src = format!("{DATA_DIRECTIVE} {ASSEMBLED_DATA_NAME}:");
+    let mut rest = data;
+    while let Some(val) = rest.first() {
+        let run = rest.iter().take_while(|v| data_char(**v).is_some()).count();
+        if run < 2 {
+            src.push_str(&format!(" {val}"));
+            rest = &rest[1..];
+            continue;
+        }
+
+        src.push_str(" \"");
+        for c in rest[..run].iter().filter_map(|v| data_char(*v)) {
+            match c {
+                '\n' => src.push_str("\\n"),
+                '\t' => src.push_str("\\t"),
+                '"' | '\\' => src.extend(['\\', c]),
+                c => src.push(c),
+            }
+        }
+        src.push('"');
+        rest = &rest[run..];
+    }
+    src.push('\n');
+
+    src
 }
 
 pub fn assemble(source: &[Instruction]) -> String {
@@ -346,3 +1176,239 @@ pub fn assemble(source: &[Instruction]) -> String {
         })
         .collect::<String>()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alias_error(src: &str) -> (usize, String) {
+        match parse_aliases(src) {
+            Err(Panic::ParseError { message, line, .. }) => (line, message),
+            other => panic!("{other:?}"),
+        }
+    }
+
+    #[test]
+    fn aliases_parse_to_canonical_instructions() {
+        let opts = ParseOptions {
+            aliases: parse_aliases(";; скорочення\n\nп = клади\nс=сума ;; додавання\n").unwrap(),
+            ..Default::default()
+        };
+        let (program, ..) = disassemble("п 1\nп 2\nп? 0\nс", &opts).unwrap();
+        assert_eq!(program[2].kind, InstructionKind::Push);
+        assert!(program[2].conditional);
+        assert_eq!(
+            assemble(&program),
+            "клади 1_зціл\nклади 2_зціл\nклади? 0_зціл\nсума\n"
+        );
+    }
+
+    #[test]
+    fn alias_collisions_are_refused_at_their_line() {
+        assert_eq!(
+            alias_error("п=клади\n\nкинь=сума\n"),
+            (
+                3,
+                "файл псевдонімів: псевдонім \"кинь\" збігається з інструкцією".to_string()
+            )
+        );
+        assert_eq!(
+            alias_error("п=клади\n;; ще раз\nп=кинь\n"),
+            (
+                3,
+                "файл псевдонімів: псевдонім \"п\" вже визначено".to_string()
+            )
+        );
+        assert_eq!(alias_error("т=таблиця\n").0, 1);
+        assert_eq!(alias_error("таблиця=клади\n").0, 1);
+    }
+
+    #[test]
+    fn predefined_names_are_values() {
+        let opts = ParseOptions {
+            strict_types: true,
+            ..Default::default()
+        };
+        let (program, ..) = disassemble(
+            "клади ТИП_ЗЦІЛ\nклади ТИП_ЦІЛ\nклади ТИП_ДРОБ\nсис файл_писати",
+            &opts,
+        )
+        .unwrap();
+        assert_eq!(
+            assemble(&program),
+            "клади 1_ціл\nклади 2_ціл\nклади 3_ціл\nсис 3_ціл\n"
+        );
+        match disassemble("клади 1\nТИП_ЦІЛ: кинь", &Default::default()) {
+            Err(Panic::ParseError { message, line, col }) => {
+                assert_eq!((line, col), (2, 1));
+                assert_eq!(message, "ім'я \"ТИП_ЦІЛ\" зарезервовано");
+            }
+            other => panic!("{other:?}"),
+        }
+    }
+
+    #[test]
+    fn malformed_aliases_are_refused_at_their_line() {
+        for (src, line) in [
+            ("п=клади\nс\n", 2),
+            ("п=нема\n", 1),
+            ("\n12=клади\n", 2),
+            ("1.5_дроб=клади\n", 1),
+            ("п:=клади\n", 1),
+            ("п?=клади\n", 1),
+            ("п р=клади\n", 1),
+            ("=клади\n", 1),
+        ] {
+            assert_eq!(alias_error(src).0, line, "{src:?}");
+        }
+    }
+
+    fn push(val: Value) -> Instruction {
+        Instruction {
+            kind: InstructionKind::Push,
+            operand: val,
+            conditional: false,
+        }
+    }
+
+    #[test]
+    fn chunk_decodes_from_the_beginning() {
+        let mut bytes = serialize(push(Value::Float(0.5))).to_vec();
+        bytes.extend(serialize(push(Value::Int(-1))));
+        let (inst, size) = disassemble_chunk(&bytes).unwrap();
+        assert_eq!(size, INST_CHUNCK_SIZE);
+        assert_eq!(inst.to_string(), "клади 0.5_дроб");
+
+        assert!(matches!(
+            disassemble_chunk(&bytes[..INST_CHUNCK_SIZE - 1]),
+            Err(Panic::CorruptedBytecode(_))
+        ));
+    }
+
+    #[test]
+    fn decoding_resyncs_after_a_corrupted_chunk() {
+        let program = [
+            push(Value::Uint(1)),
+            push(Value::Int(2)),
+            push(Value::Float(3.0)),
+            Instruction::default(),
+        ];
+        let mut bytes = program.map(serialize).concat();
+        // Unknown opcode in the second chunk and a bad option byte in the third
+        bytes[INST_CHUNCK_SIZE] = 250;
+        bytes[2 * INST_CHUNCK_SIZE + 1] = 12;
+
+        let decoded = disassemble_all(&bytes).collect::<Vec<_>>();
+        assert_eq!(decoded.len(), program.len());
+        assert_eq!(decoded[0].as_ref().unwrap().to_string(), "клади 1_ціл");
+        assert!(matches!(decoded[1], Err(Panic::CorruptedBytecode(_))));
+        assert!(matches!(decoded[2], Err(Panic::CorruptedBytecode(_))));
+        assert_eq!(decoded[3].as_ref().unwrap().to_string(), "неоп");
+    }
+
+    #[test]
+    fn decoding_reports_a_truncated_tail() {
+        let bytes = [
+            serialize(push(Value::Uint(1))),
+            serialize(Instruction::default()),
+        ]
+        .concat();
+        let decoded = disassemble_all(&bytes[..bytes.len() - 3]).collect::<Vec<_>>();
+        assert_eq!(decoded.len(), 2);
+        assert!(decoded[0].is_ok());
+        assert!(matches!(decoded[1], Err(Panic::CorruptedBytecode(_))));
+    }
+
+    #[test]
+    fn data_follows_the_instructions() {
+        let data = [Value::Uint(1), Value::Float(0.5), Value::Int(-3)];
+        let mut bytes = serialize(push(Value::Uint(1))).to_vec();
+        bytes.extend(data.map(serialize_data).concat());
+
+        let (code, rest) = split_data(&bytes);
+        assert_eq!(code.len(), INST_CHUNCK_SIZE);
+        let decoded = disassemble_data(rest)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(decoded, data);
+        assert_eq!(split_data(code), (code, &[][..]));
+    }
+
+    #[test]
+    fn corrupted_data_is_refused() {
+        let val = serialize_data(Value::Uint(1));
+        let mut conditional = val;
+        conditional[1] += 1;
+        let mut null = val;
+        null[1] = 0;
+
+        for bytes in [
+            [val, serialize(push(Value::Uint(1)))].concat(),
+            val[..INST_CHUNCK_SIZE - 1].to_vec(),
+            conditional.to_vec(),
+            null.to_vec(),
+        ] {
+            let (code, data) = split_data(&bytes);
+            assert!(code.is_empty());
+            assert!(
+                matches!(
+                    disassemble_data(data).find_map(Result::err),
+                    Some(Panic::CorruptedBytecode(_))
+                ),
+                "{bytes:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn data_is_assembled_into_one_directive() {
+        assert_eq!(assemble_data(&[]), "");
+        let chars = |s: &str| {
+            s.chars()
+                .map(|c| Value::Uint(c as usize))
+                .collect::<Vec<_>>()
+        };
+
+        let mut data = chars("ок\n");
+        data.extend([Value::Uint(7), Value::Int(65)]);
+        data.extend(chars("A"));
+        data.push(Value::Uint(0));
+        data.extend(chars("\"\\\t"));
+        assert_eq!(
+            assemble_data(&data),
+            "дані дані_програми: \"ок\\n\" 7_ціл 65_зціл 65_ціл 0_ціл \"\\\"\\\\\\t\"\n"
+        );
+
+        let (program, _, values, _) =
+            disassemble(assemble_data(&data), &ParseOptions::default()).unwrap();
+        assert!(program.is_empty());
+        assert_eq!(values, data);
+    }
+
+    #[test]
+    fn table_is_indexed_by_opcode() {
+        for (code, info) in INSTRUCTIONS.iter().enumerate() {
+            assert_eq!(info.kind as usize, code, "{}", info.mnemonic);
+            assert_eq!(InstructionKind::try_from_idx(code as u8), Some(info.kind));
+        }
+    }
+
+    #[test]
+    fn mnemonics_round_trip_through_the_table() {
+        for info in INSTRUCTIONS {
+            assert_eq!(InstructionKind::try_parse(info.mnemonic), Ok(info.kind));
+            assert_eq!(info.kind.to_string(), info.mnemonic);
+            assert_eq!(info.kind.info().mnemonic, info.mnemonic);
+            assert_eq!(
+                INSTRUCTIONS
+                    .iter()
+                    .filter(|i| i.mnemonic == info.mnemonic || i.alias == info.alias)
+                    .count(),
+                1,
+                "{} / {}",
+                info.mnemonic,
+                info.alias
+            );
+        }
+    }
+}
@@ -1,5 +1,14 @@
-use crate::{Instruction, InstructionKind, Panic, Value};
-use std::{error, fmt};
+use crate::{
+    usm::{InstructionInfo, Operand, INSTRUCTIONS},
+    watch::WatchExpr,
+    Instruction, InstructionKind, Panic, Value, READ_TOKEN_CAPACITY, VM,
+};
+use std::{
+    error, fmt, fs,
+    io::{self, BufWriter, Write},
+    path::Path,
+    process,
+};
 
 pub fn print_usage<S: AsRef<str>>(sub: S) {
     let general = "./uvm [ПІДКОМАНДА] [ОПЦ] <ФАЙЛ>
@@ -9,6 +18,9 @@ pub fn print_usage<S: AsRef<str>>(sub: S) {
     usm - перекласти <ФАЙЛ> з байткодом інструкцій UVM на USM (assembly)
     dusm - перекласти <ФАЙЛ> формату USM (assembly) на байткод з інструкціями UVM
     dump - прочитати <ФАЙЛ> без виконання інструкцій та показати лист цих інструкцій
+    doc-isa - показати довідник інструкцій UVM
+    patch - замінити інструкції у <ФАЙЛІ> з байткодом
+    hash - показати хеш інструкцій та даних з <ФАЙЛУ> (однаковий для USM та байткоду)
 
 [ОПЦ]
     -h - показати це повідомлення";
@@ -18,14 +30,35 @@ pub fn print_usage<S: AsRef<str>>(sub: S) {
 [ОПЦ]
     -usm - перекласти <ФАЙЛ> формату USM (assembly) на байткод інструкцій UVM та виконати їх
     -l <ЧИС> - встановити ліміт на кількість виконуваних інструкцій
-    -ds - показати всі зміни стеку на протязі виконня програми
+    -ds - показати всі зміни стеку (та регістри після \"у_рег\", зайняту пам'ять після \"збережи\") на протязі виконня програми
     -di - показати лист виконаних інструкцій
+    -v - показати підсумок виконання програми
+    --aliases <ФАЙЛ> - використати псевдоніми інструкцій з <ФАЙЛУ> (рядки \"псевдонім=канонічна\")
+    --deny <КАТЕГОРІЯ>,.. - заборонити інструкції категорій io, natives, memory, nondeterminism
+    --strict-types - вимагати суфікс типу (_ціл, _зціл, _дроб) для кожного числового операнда
+    --watch-expr <ВИРАЗ> - показувати значення <ВИРАЗУ> кожного разу, коли воно змінюється:
+        стек[<ЧИС>] - значення на глибині <ЧИС> стеку
+        рег[<ЧИС>] - значення регістра <ЧИС>
+        глибина - розмір стеку
+    --snapshot-at <ЛЕЙБЛ>,.. - записувати стек кожного разу, коли виконання доходить до <ЛЕЙБЛУ>
+    --snapshot-file <ФАЙЛ> - записувати знімки стеку до <ФАЙЛУ> замість stdout
+    --warn-coercion - попереджати про арифметику над значеннями різних типів (раз на адресу)
+    --color - розфарбувати значення у -ds за їх типом (не діє, якщо задано NO_COLOR)
+    -q - не попереджати про програму без інструкцій
+    --seed <ЧИС> - почати послідовність \"випадк\" з зерна <ЧИС> замість поточного часу
+    --call-depth <ЧИС> - обмежити вкладеність викликів \"виклик\" до <ЧИС>
+    --allow-fs - дозволити системним викликам \"файл_читати\" та \"файл_писати\" доступ до файлів
+    --fs-root <КАТАЛОГ> - дозволити доступ лише до файлів у <КАТАЛОЗІ> (разом з --allow-fs)
     -h - показати це повідомлення";
 
     let dusm = "./uvm dusm [ОПЦ] <ФАЙЛ>
 
 [ОПЦ]
     -o <ВИХІДНИЙ ФАЙЛ> - записати байткод інструкцій до <ВИХІДНОГО ФАЙЛУ>
+    --fold, -O2 - обчислити послідовності констант під час перекладу
+    --inline-threshold <ЧИС> - вбудувати виклики підпрограм довжиною до <ЧИС> інструкцій без вкладених викликів
+    --aliases <ФАЙЛ> - використати псевдоніми інструкцій з <ФАЙЛУ> (рядки \"псевдонім=канонічна\")
+    --strict-types - вимагати суфікс типу (_ціл, _зціл, _дроб) для кожного числового операнда
     -h - показати це повідомлення";
 
     let usm = "./uvm usm [ОПЦ] <ФАЙЛ>
@@ -34,15 +67,45 @@ pub fn print_usage<S: AsRef<str>>(sub: S) {
     -o <ВИХІДНИЙ ФАЙЛ> - записати перекладені на USM (assembly) інструкціЇ до <ВИХІДНОГО ФАЙЛУ>
     -h - показати це повідомлення";
 
-    let dump = "./uvm usm [ОПЦ] <ФАЙЛ>
+    let dump = "./uvm dump [ОПЦ] <ФАЙЛ>
 
 [ОПЦ]
     -l <ЧИС> - встановити ліміт на кількість показаних інструкцій
+    -o <ВИХІДНИЙ ФАЙЛ> - записати лист інструкцій до <ВИХІДНОГО ФАЙЛУ>
+    --float-precision <ЧИС> - показувати дроби з <ЧИС> знаками після крапки (з втратою точності)
+    --strict-types - вимагати суфікс типу (_ціл, _зціл, _дроб) для кожного числового операнда
+    --page <ЧИС> - показувати по <ЧИС> інструкцій та чекати на Enter (тільки у терміналі)
+    --find <ТЕКСТ> - показати тільки інструкції, що містять <ТЕКСТ>, з їх адресами та контекстом
+    --strict-empty - завершитися з помилкою, якщо програма не містить інструкцій
+    -h - показати це повідомлення";
+
+    let doc_isa = "./uvm doc-isa [ОПЦ]
+
+[ОПЦ]
+    -o <ВИХІДНИЙ ФАЙЛ> - записати довідник до <ВИХІДНОГО ФАЙЛУ>
+    --format <text|md> - формат довідника: простий текст (за замовчуванням) або Markdown
+    -h - показати це повідомлення";
+
+    let patch = "./uvm patch [ОПЦ] <ФАЙЛ>
+
+[ОПЦ]
+    --set <АДРЕСА> <ІНСТРУКЦІЯ> - замінити інструкцію за <АДРЕСОЮ> на <ІНСТРУКЦІЮ> USM, наприклад \"клади 7_ціл\"
+    --nop <АДРЕСА>..<АДРЕСА> - замінити інструкції у діапазоні адрес (включно) на \"неоп\"
+    -o <ВИХІДНИЙ ФАЙЛ> - записати результат до <ВИХІДНОГО ФАЙЛУ> замість <ФАЙЛУ>
+    -h - показати це повідомлення";
+
+    let hash = "./uvm hash [ОПЦ] <ФАЙЛ>
+
+[ОПЦ]
+    -usm - перекласти <ФАЙЛ> формату USM (assembly) перед обчисленням хешу
     -h - показати це повідомлення";
 
     eprintln!(
         "{}",
         match sub.as_ref() {
+            "doc-isa" => doc_isa,
+            "hash" => hash,
+            "patch" => patch,
             "emu" => emu,
             "dusm" => dusm,
             "usm" => usm,
@@ -52,18 +115,217 @@ pub fn print_usage<S: AsRef<str>>(sub: S) {
     );
 }
 
+// Reads a USM source, dropping the UTF-8 BOM that some editors put at the start
+// and the `\r` of Windows line endings
+pub fn read_source<P: AsRef<Path>>(path: P) -> Result<String, Panic> {
+    const BOM: &[u8] = b"\xEF\xBB\xBF";
+    let bytes = fs::read(path).map_err(Panic::ReadFileErr)?;
+    let skipped = if bytes.starts_with(BOM) { BOM.len() } else { 0 };
+    let src = std::str::from_utf8(&bytes[skipped..]).map_err(|e| Panic::InvalidEncoding {
+        byte_offset: skipped + e.valid_up_to(),
+    })?;
+
+    Ok(src.replace("\r\n", "\n"))
+}
+
+// Refuses an output path that names the same file as the input, however it is spelled.
+// "-" stands for stdin/stdout and never matches a file.
+pub fn ensure_not_input<P: AsRef<Path>>(input: P, output: Option<P>) -> Result<(), Panic> {
+    let Some(output) = output else {
+        return Ok(());
+    };
+    let (input, output) = (input.as_ref(), output.as_ref());
+    if input == Path::new("-") || output == Path::new("-") {
+        return Ok(());
+    }
+
+    match (fs::canonicalize(input), fs::canonicalize(output)) {
+        (Ok(i), Ok(o)) if i == o => Err(Panic::OutputIsInput(output.display().to_string())),
+        _ => Ok(()),
+    }
+}
+
+// Writes into a temporary file next to `path` and renames it over `path` only
+// when everything was written, so a failed write never leaves a truncated file
+pub fn write_atomically<P, F>(path: P, write: F) -> Result<(), Panic>
+where
+    P: AsRef<Path>,
+    F: FnOnce(&mut dyn Write) -> io::Result<()>,
+{
+    let path = path.as_ref();
+    let tmp = path.with_file_name(format!(
+        ".{name}.{pid}.tmp",
+        name = path.file_name().unwrap_or_default().to_string_lossy(),
+        pid = process::id()
+    ));
+
+    fs::File::create(&tmp)
+        .and_then(|f| {
+            let mut out = BufWriter::new(f);
+            write(&mut out)?;
+            out.into_inner().map_err(|e| e.into_error())?.sync_all()
+        })
+        .and_then(|_| fs::rename(&tmp, path))
+        .map_err(|e| {
+            _ = fs::remove_file(&tmp);
+            Panic::OutputNotReplaced(e)
+        })
+}
+
+pub fn colorize(value: Value) -> String {
+    let color = match value {
+        Value::Float(_) => 36,
+        Value::Uint(_) => 32,
+        Value::Int(_) => 33,
+        Value::Null => 90,
+    };
+    format!("\x1b[{color}m{value}\x1b[0m")
+}
+
+pub fn render_panic_context(vm: &VM, err: &Panic) -> String {
+    const WINDOW: usize = 3;
+    const STACK_TOP: usize = 5;
+
+    let mut ctx = String::new();
+    let start = vm.inst_ptr.saturating_sub(WINDOW);
+    for (addr, inst) in vm
+        .program
+        .iter()
+        .enumerate()
+        .take(vm.inst_ptr + WINDOW + 1)
+        .skip(start)
+    {
+        if addr == vm.inst_ptr {
+            ctx.push_str(&format!("-> {addr}: {inst}    <- {err}\n"));
+        } else {
+            ctx.push_str(&format!("   {addr}: {inst}\n"));
+        }
+    }
+
+    if let Some((n, line)) = vm.source.get(vm.inst_ptr).and_then(|info| {
+        let n = info.line();
+        Some((n, vm.source_text.lines().nth(n.checked_sub(1)?)?))
+    }) {
+        ctx.push_str(&format!("РЯДОК {n}: {line}\n", line = line.trim()));
+    }
+
+    let stack = vm.stack.get_all();
+    let top = &stack[stack.len().saturating_sub(STACK_TOP)..];
+    ctx.push_str(&format!(
+        "СТЕК [{size}] :{more}",
+        size = stack.len(),
+        more = if top.len() < stack.len() { " .." } else { "" }
+    ));
+    for v in top {
+        ctx.push_str(&format!(" {v}"));
+    }
+    ctx.push('\n');
+
+    ctx
+}
+
+pub fn isa_reference(markdown: bool) -> String {
+    let row = |i: &InstructionInfo| {
+        let operand = match i.operand {
+            Operand::None => "ні",
+            Operand::Required => "так",
+            Operand::Optional => "можна",
+        };
+        if markdown {
+            format!(
+                "| `{}` | `{}` | {} | {operand} | `{}` | {} | {} |\n",
+                i.mnemonic,
+                i.alias,
+                i.kind as u8,
+                i.stack_effect,
+                i.description_ua,
+                i.description_en
+            )
+        } else {
+            format!(
+                "{:<8} {:<8} {:<3} {operand:<7} {:<26} {} / {}\n",
+                i.mnemonic,
+                i.alias,
+                i.kind as u8,
+                i.stack_effect,
+                i.description_ua,
+                i.description_en
+            )
+        }
+    };
+
+    let mut doc = if markdown {
+        "| Мнемоніка | Alias | Код | Операнд | Стек | Опис | Description |\n\
+         |---|---|---|---|---|---|---|\n"
+            .to_string()
+    } else {
+        format!(
+            "{:<8} {:<8} {:<3} {:<7} {:<26} {}\n",
+            "МНЕМ", "ALIAS", "КОД", "ОПЕРАНД", "СТЕК", "ОПИС / DESCRIPTION"
+        )
+    };
+    doc.extend(INSTRUCTIONS.iter().map(row));
+
+    doc
+}
+
+pub fn find_with_context(program: &[Instruction], needle: &str, context: usize) -> Vec<usize> {
+    let mut addrs = Vec::<usize>::new();
+    for (addr, _) in program
+        .iter()
+        .enumerate()
+        .filter(|(_, inst)| inst.to_string().contains(needle))
+    {
+        let start = addrs
+            .last()
+            .map(|last| last + 1)
+            .unwrap_or(0)
+            .max(addr.saturating_sub(context));
+        addrs.extend(start..(addr + context + 1).min(program.len()));
+    }
+
+    addrs
+}
+
+pub fn page_is_full(printed: usize, page: Option<usize>, is_tty: bool) -> bool {
+    match page {
+        Some(p) if is_tty => printed != 0 && printed.is_multiple_of(p),
+        _ => false,
+    }
+}
+
 impl<T: Copy + Default, const N: usize> Default for Array<T, N> {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// Fixed-capacity array stored inline: `N` items followed by the number of live ones,
+/// so `Array<Value, 1024>` takes `1024 * size_of::<Value>() + size_of::<usize>()` bytes.
+/// It is intentionally not `Copy`, cloning copies only the live items.
+#[repr(C)]
 #[derive(Debug)]
 pub struct Array<T, const N: usize> {
     pub items: [T; N],
     pub size: usize,
 }
 
+const _: () = assert!(
+    std::mem::size_of::<Array<Value, 1024>>()
+        == 1024 * std::mem::size_of::<Value>() + std::mem::size_of::<usize>()
+);
+
+impl<T: Copy + Default, const N: usize> Clone for Array<T, N> {
+    fn clone(&self) -> Self {
+        let mut items = [T::default(); N];
+        items[..self.size].copy_from_slice(self.get_all());
+        Self {
+            items,
+            size: self.size,
+        }
+    }
+}
+
 impl<T: Copy + Default, const N: usize> Array<T, N> {
     pub fn new() -> Self {
         Self {
@@ -80,19 +342,20 @@ impl<T: Copy + Default, const N: usize> Array<T, N> {
         &mut self.items[self.size - (idx + 1)]
     }
 
-    pub fn get_last(&self) -> T {
-        self.get_from_end(0)
-    }
-
-    pub fn get_last_mut(&mut self) -> &mut T {
-        self.get_from_end_mut(0)
+    // Removes the item and moves everything above it one place down
+    pub fn remove_from_end(&mut self, idx: usize) -> T {
+        let at = self.size - (idx + 1);
+        let item = self.items[at];
+        self.items.copy_within(at + 1..self.size, at);
+        self.size -= 1;
+        item
     }
 
-    pub fn get(&self, idx: usize) -> T {
-        self.items[idx]
+    pub fn _get_last(&self) -> T {
+        self.get_from_end(0)
     }
 
-    pub fn _get_mut(&mut self, idx: usize) -> &mut T {
+    pub fn get_mut(&mut self, idx: usize) -> &mut T {
         &mut self.items[idx]
     }
 
@@ -106,22 +369,34 @@ impl<T: Copy + Default, const N: usize> Array<T, N> {
         self.items[self.size]
     }
 
-    pub fn _replace(&mut self, idx: usize, item: T) {
-        self.items[idx] = item;
-    }
-
     pub fn get_all(&self) -> &[T] {
         &self.items[..self.size]
     }
 }
 
+impl<T: Copy + Default + fmt::Display, const N: usize> fmt::Display for Array<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, item) in self.get_all().iter().enumerate() {
+            if i != 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{item}")?;
+        }
+        Ok(())
+    }
+}
+
+// Floats are printed in the shortest form that is parsed back into the same value
+// (with an exponent for very large or small ones), unless a precision is requested
+// (`{:.3}`), which is lossy and meant only for people
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Value::Float(v) => write!(f, "{v}_дроб"),
-            Value::Uint(v) => write!(f, "{v}_ціл"),
-            Value::Int(v) => write!(f, "{v}_зціл"),
-            Value::Null => write!(f, "_"),
+        match (self, f.precision()) {
+            (Value::Float(v), Some(p)) => write!(f, "{v:.p$}_дроб"),
+            (Value::Float(v), _) => write!(f, "{v:?}_дроб"),
+            (Value::Uint(v), _) => write!(f, "{v}_ціл"),
+            (Value::Int(v), _) => write!(f, "{v}_зціл"),
+            (Value::Null, _) => write!(f, "_"),
         }
     }
 }
@@ -130,34 +405,30 @@ impl fmt::Display for Instruction {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "{kind}{cond} {oper}",
+            "{kind}{cond}",
             kind = self.kind,
             cond = if self.conditional { "?" } else { "" },
-            oper = self.operand,
-        )
+        )?;
+        match f.precision() {
+            _ if self.operand.is_null() => Ok(()),
+            Some(p) => write!(f, " {:.p$}", self.operand),
+            _ => write!(f, " {}", self.operand),
+        }
     }
 }
 
 impl fmt::Display for InstructionKind {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        use InstructionKind::*;
+        write!(f, "{}", self.info().mnemonic)
+    }
+}
+
+impl fmt::Display for WatchExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Nop => write!(f, "неоп"),
-            Drop => write!(f, "кинь"),
-            Dup => write!(f, "копію"),
-            Push => write!(f, "клади"),
-            Jump => write!(f, "крок"),
-            Eq => write!(f, "рівн"),
-            Sub => write!(f, "різн"),
-            Mul => write!(f, "множ"),
-            Div => write!(f, "діли"),
-            Sum => write!(f, "сума"),
-            NotEq => write!(f, "нерівн"),
-            Extern => write!(f, "ззовні"),
-            Return => write!(f, "вертай"),
-            Call => write!(f, "клич"),
-            Halt => write!(f, "кінчай"),
-            Swap => write!(f, "міняй"),
+            WatchExpr::Stack(idx) => write!(f, "стек[{idx}]"),
+            WatchExpr::Register(idx) => write!(f, "рег[{idx}]"),
+            WatchExpr::Depth => write!(f, "глибина"),
         }
     }
 }
@@ -169,12 +440,195 @@ impl fmt::Display for Panic {
             StackOverflow => write!(f, "Переповнений Стек"),
             StackUnderflow => write!(f, "Незаповненість Стека"),
             ValueOverflow => write!(f, "Перевищено Ліміт Значення"),
-            ParseError(e) => write!(f, "Помилка Перекладу: {e}"),
+            ValueUnderflow => write!(f, "Від'ємне Значення Для Беззнакового Типу"),
+            ParseError { message, line: 0, .. } => write!(f, "Помилка Перекладу: {message}"),
+            ParseError { message, line, col } => {
+                write!(f, "Помилка Перекладу: {line}:{col}: {message}")
+            }
+            CorruptedBytecode(e) => write!(f, "Пошкоджений Байткод: {e}"),
             ReadFileErr(err) => write!(f, "Неможливо Прочитати Файл: {err}"),
             WriteToFileErr(err) => write!(f, "Помилка Запусу До Файлу: {err}"),
+            OutputNotReplaced(err) => write!(
+                f,
+                "Помилка Запусу До Файлу: {err} (попередній вміст файлу не змінено)"
+            ),
+            OutputIsInput(path) => {
+                write!(f, "Вихідний Файл Збігається З Вхідним: {path}")
+            }
+            CallStackUnderflow => write!(f, "Немає Відкритого Кадру"),
+            ReturnStackOverflow => write!(f, "Переповнений Стек Повернень"),
+            ReturnStackUnderflow => write!(f, "Незаповненість Стека Повернень"),
+            EmptyProgram => write!(f, "Програма Не Містить Інструкцій"),
+            InvalidEncoding { byte_offset } => write!(
+                f,
+                "Неправильне Кодування: байт {byte_offset} не є UTF-8 (можливо, це файл з байткодом, а не USM?)"
+            ),
             DivByZero => write!(f, "Ділення На Нуль"),
+            FloatDomain(v) => write!(f, "Значення Поза Областю Визначення: {v}"),
+            InvalidOperandValue(v) => write!(f, "Неприпустиме Значення Операнда: {v}"),
+            MemoryOutOfBounds(addr) => write!(f, "Адреса Поза Пам'яттю: {addr}"),
+            UnknownSyscall(n) => write!(f, "Невідомий Системний Виклик: {n}"),
+            PermissionDenied(path) => write!(f, "Доступ Заборонено: {path}"),
+            AssertionFailed { inst_ptr } => {
+                write!(f, "Перевірку Не Пройдено: за адресою {inst_ptr}")
+            }
+            InputError(text) => write!(f, "Помилка Вводу: \"{text}\" не є числом"),
+            InputTooLong => write!(
+                f,
+                "Помилка Вводу: число довше за {READ_TOKEN_CAPACITY} байтів"
+            ),
+            EndOfInput => write!(f, "Кінець Вводу"),
+            CapabilityDenied { kind, inst_ptr } => {
+                write!(f, "Заборонена Інструкція: \"{kind}\" за адресою {inst_ptr}")
+            }
         }
     }
 }
 
 impl error::Error for Panic {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn program(src: &str) -> Vec<Instruction> {
+        crate::usm::disassemble(src, &Default::default()).unwrap().0
+    }
+
+    #[test]
+    fn failed_write_keeps_the_original() {
+        let dir = std::env::temp_dir().join(format!("uvm-atomic-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("out.bin");
+        fs::write(&path, "старе").unwrap();
+
+        let result = write_atomically(&path, |out| {
+            out.write_all(b"half")?;
+            Err(io::Error::other("зламано"))
+        });
+        assert!(matches!(result, Err(Panic::OutputNotReplaced(_))));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "старе");
+        assert_eq!(
+            fs::read_dir(&dir).unwrap().count(),
+            1,
+            "the temp file is left"
+        );
+
+        write_atomically(&path, |out| out.write_all("нове".as_bytes())).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "нове");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        _ = fs::remove_dir_all(dir);
+    }
+
+    // Runs the program until the first panic
+    fn fail(src: &str) -> (VM, Panic) {
+        let mut vm = VM {
+            program: program(src),
+            ..Default::default()
+        };
+        loop {
+            if let Err(e) = vm.execute_instruction() {
+                return (vm, e);
+            }
+        }
+    }
+
+    fn window(ctx: &str) -> Vec<&str> {
+        ctx.lines()
+            .filter(|l| !l.starts_with("СТЕК"))
+            .map(|l| l[3..].split(':').next().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn panic_context_surrounds_the_failing_instruction() {
+        let (vm, e) = fail("неоп\nнеоп\nнеоп\nнеоп\nклади 1\nсума\nнеоп\nнеоп\nнеоп\nнеоп");
+        let ctx = render_panic_context(&vm, &e);
+        assert_eq!(window(&ctx), ["2", "3", "4", "5", "6", "7", "8"]);
+        assert!(ctx
+            .lines()
+            .any(|l| l == "-> 5: сума    <- Незаповненість Стека"));
+        assert_eq!(ctx.matches("->").count(), 1);
+        // Both operands were popped before the failure
+        assert!(ctx.ends_with("СТЕК [0] :\n"));
+    }
+
+    #[test]
+    fn panic_context_is_clipped_at_the_program_bounds() {
+        let (vm, e) = fail("кинь\nнеоп\nнеоп\nнеоп\nнеоп");
+        let ctx = render_panic_context(&vm, &e);
+        assert_eq!(window(&ctx), ["0", "1", "2", "3"]);
+        assert!(ctx.starts_with("-> 0: кинь"));
+        assert!(ctx.ends_with("СТЕК [0] :\n"));
+
+        let (vm, e) = fail("неоп\nнеоп\nнеоп\nнеоп\nкинь");
+        let ctx = render_panic_context(&vm, &e);
+        assert_eq!(window(&ctx), ["1", "2", "3", "4"]);
+        assert!(ctx.contains("-> 4: кинь"));
+    }
+
+    #[test]
+    fn panic_context_shows_the_stack_top() {
+        let (vm, e) = fail("клади 1\nклади 2\nклади 3\nклади 4\nклади 5\nклади 6\nмежа 1");
+        assert!(render_panic_context(&vm, &e)
+            .ends_with("СТЕК [6] : .. 2_зціл 3_зціл 4_зціл 5_зціл 6_зціл\n"));
+    }
+
+    #[test]
+    fn isa_reference_lists_every_instruction_once() {
+        for markdown in [false, true] {
+            let doc = isa_reference(markdown);
+            let mnemonics = doc
+                .lines()
+                .skip(if markdown { 2 } else { 1 })
+                .filter_map(|l| l.split_whitespace().find(|w| *w != "|"))
+                .map(|w| w.trim_matches('`'))
+                .collect::<Vec<_>>();
+            let expected = INSTRUCTIONS.iter().map(|i| i.mnemonic).collect::<Vec<_>>();
+            assert_eq!(mnemonics, expected);
+        }
+    }
+
+    #[test]
+    fn find_keeps_two_lines_of_context() {
+        let program = program("неоп\nнеоп\nнеоп\nклади 7\nнеоп\nнеоп\nнеоп\nнеоп");
+        assert_eq!(find_with_context(&program, "клади", 2), [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn find_clips_context_at_the_program_bounds() {
+        let program = program("клади 1\nнеоп\nнеоп\nнеоп\nклади 2");
+        assert_eq!(find_with_context(&program, "клади", 2), [0, 1, 2, 3, 4]);
+        assert_eq!(find_with_context(&program, "клади 2", 1), [3, 4]);
+    }
+
+    #[test]
+    fn find_merges_overlapping_context() {
+        let program = program("сума\nнеоп\nсума\nнеоп\nнеоп\nнеоп\nнеоп\nсума");
+        assert_eq!(find_with_context(&program, "сума", 1), [0, 1, 2, 3, 6, 7]);
+        assert!(find_with_context(&program, "множ", 1).is_empty());
+    }
+
+    #[test]
+    fn find_matches_operands() {
+        let program = program("клади 5\nклади 50_ціл\nклади 6");
+        assert_eq!(find_with_context(&program, "5", 0), [0, 1]);
+        assert_eq!(find_with_context(&program, "_ціл", 0), [1]);
+    }
+
+    #[test]
+    fn page_is_full_only_on_a_tty() {
+        assert!(page_is_full(3, Some(3), true));
+        assert!(page_is_full(6, Some(3), true));
+        assert!(!page_is_full(3, Some(3), false));
+        assert!(!page_is_full(6, Some(3), false));
+    }
+
+    #[test]
+    fn page_is_full_after_every_page() {
+        assert!(!page_is_full(0, Some(3), true));
+        assert!(!page_is_full(2, Some(3), true));
+        assert!(!page_is_full(4, Some(3), true));
+        assert!(!page_is_full(3, None, true));
+    }
+}
@@ -0,0 +1,108 @@
+use crate::{Value, REGISTERS_COUNT, VM};
+
+// Expressions accepted by `emu --watch-expr`:
+//     стек[N] - value at the depth N of the stack (`_` if the stack is not that deep)
+//     рег[N]  - value of the register N, 0 until written
+//     глибина - current size of the stack
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WatchExpr {
+    Stack(usize),
+    Register(usize),
+    Depth,
+}
+
+impl WatchExpr {
+    pub fn try_parse<T: AsRef<str>>(src: T) -> Result<Self, String> {
+        let src = src.as_ref().trim();
+        if src == "глибина" {
+            return Ok(WatchExpr::Depth);
+        }
+
+        let (name, idx) = src
+            .strip_suffix(']')
+            .and_then(|s| s.split_once('['))
+            .ok_or(format!("неможливо розібрати вираз \"{src}\""))?;
+        let idx = idx
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| format!("неправельний індекс \"{idx}\" у виразі \"{src}\""))?;
+
+        match name.trim() {
+            "стек" => Ok(WatchExpr::Stack(idx)),
+            "рег" if idx < REGISTERS_COUNT => Ok(WatchExpr::Register(idx)),
+            "рег" => Err(format!(
+                "немає регістра {idx} у виразі \"{src}\", їх лише {REGISTERS_COUNT}"
+            )),
+            name => Err(format!("невідоме джерело \"{name}\" у виразі \"{src}\"")),
+        }
+    }
+
+    pub fn eval(&self, vm: &VM) -> Value {
+        match *self {
+            WatchExpr::Stack(idx) if idx < vm.stack.size => vm.stack.get_from_end(idx),
+            WatchExpr::Stack(_) => Value::Null,
+            WatchExpr::Register(idx) => vm.register(Value::Uint(idx)).unwrap_or_default(),
+            WatchExpr::Depth => Value::Uint(vm.stack.size),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_form() {
+        assert_eq!(WatchExpr::try_parse("стек[0]"), Ok(WatchExpr::Stack(0)));
+        assert_eq!(
+            WatchExpr::try_parse(" стек[ 12 ] "),
+            Ok(WatchExpr::Stack(12))
+        );
+        assert_eq!(WatchExpr::try_parse("рег[3]"), Ok(WatchExpr::Register(3)));
+        assert_eq!(WatchExpr::try_parse("глибина"), Ok(WatchExpr::Depth));
+    }
+
+    #[test]
+    fn display_parses_back() {
+        for expr in [
+            WatchExpr::Stack(4),
+            WatchExpr::Register(REGISTERS_COUNT - 1),
+            WatchExpr::Depth,
+        ] {
+            assert_eq!(WatchExpr::try_parse(expr.to_string()), Ok(expr));
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        for src in ["", "стек", "стек[", "стек[0", "глибина[0]", "рег[]"] {
+            assert!(WatchExpr::try_parse(src).is_err(), "{src}");
+        }
+        assert!(WatchExpr::try_parse("стек[-1]")
+            .unwrap_err()
+            .contains("неправельний індекс"));
+        assert!(WatchExpr::try_parse("пам[0]")
+            .unwrap_err()
+            .contains("невідоме джерело \"пам\""));
+        assert!(WatchExpr::try_parse(format!("рег[{REGISTERS_COUNT}]"))
+            .unwrap_err()
+            .contains("немає регістра"));
+    }
+
+    #[test]
+    fn evaluates_against_the_vm() {
+        let mut vm = VM::default();
+        assert_eq!(WatchExpr::Stack(0).eval(&vm), Value::Null);
+        assert_eq!(WatchExpr::Register(2).eval(&vm), Value::Uint(0));
+        assert_eq!(WatchExpr::Depth.eval(&vm), Value::Uint(0));
+
+        vm.stack_push(Value::Int(7)).unwrap();
+        vm.stack_push(Value::Int(8)).unwrap();
+        vm.registers[2] = Value::Float(0.5);
+        assert_eq!(WatchExpr::Stack(0).eval(&vm), Value::Int(8));
+        assert_eq!(WatchExpr::Stack(1).eval(&vm), Value::Int(7));
+        assert_eq!(WatchExpr::Stack(2).eval(&vm), Value::Null);
+        assert_eq!(WatchExpr::Register(2).eval(&vm), Value::Float(0.5));
+        assert_eq!(WatchExpr::Depth.eval(&vm), Value::Uint(2));
+    }
+}
@@ -0,0 +1,73 @@
+use std::{
+    env, fs,
+    process::{Command, Output},
+};
+
+const CHUNK: usize = 10;
+
+// `клади 7_зціл` followed by `кинь`
+const VALID: [[u8; CHUNK]; 2] = [
+    [1, 10, 7, 0, 0, 0, 0, 0, 0, 0],
+    [3, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+];
+
+fn disassemble(name: &str, bytes: &[u8]) -> Output {
+    let file = env::temp_dir().join(format!("uvm_bytecode_{}_{name}.bin", std::process::id()));
+    fs::write(&file, bytes).unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .arg("usm")
+        .arg(&file)
+        .output()
+        .unwrap();
+    fs::remove_file(&file).unwrap();
+
+    out
+}
+
+fn corrupted(name: &str, chunk: [u8; CHUNK]) -> String {
+    let mut bytes = VALID.concat();
+    bytes.extend(chunk);
+    let out = disassemble(name, &bytes);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(out.stdout.is_empty());
+
+    String::from_utf8_lossy(&out.stderr).trim_end().to_string()
+}
+
+#[test]
+fn valid_chunks_decode() {
+    let out = disassemble("valid", &VALID.concat());
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "клади 7_зціл\nкинь\n");
+}
+
+#[test]
+fn unknown_opcode_is_rejected() {
+    assert_eq!(
+        corrupted("opcode", [250, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
+        "Пошкоджений Байткод: невідомий код інструкції 250"
+    );
+}
+
+#[test]
+fn impossible_option_bytes_are_rejected() {
+    for opts in [2, 12, 20, 210, 211, 212, 255] {
+        assert_eq!(
+            corrupted(&format!("opts{opts}"), [1, opts, 0, 0, 0, 0, 0, 0, 0, 0]),
+            format!("Пошкоджений Байткод: неможливе значення опцій інструкції {opts}")
+        );
+    }
+}
+
+#[test]
+fn truncated_chunk_is_rejected() {
+    let mut bytes = VALID.concat();
+    bytes.truncate(CHUNK + 4);
+    let out = disassemble("truncated", &bytes);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("неповна інструкція розміром 4 байт"));
+}
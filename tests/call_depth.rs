@@ -0,0 +1,28 @@
+use std::{path::Path, process::Command};
+
+fn factorial(depth: &str) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .args(["emu", "--call-depth", depth])
+        .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/programs/factorial.usm"))
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn recursion_fits_the_call_depth() {
+    // 5! nests five calls
+    let out = factorial("5");
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "120_зціл\n");
+}
+
+#[test]
+fn recursion_deeper_than_the_call_depth_fails() {
+    let out = factorial("4");
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Переповнений Стек Повернень"));
+}
@@ -0,0 +1,86 @@
+use std::{
+    env, fs,
+    process::{Command, Output},
+};
+
+fn run(name: &str, src: &str, args: &[&str]) -> Output {
+    let program = env::temp_dir().join(format!("uvm_deny_{}_{name}.usm", std::process::id()));
+    fs::write(&program, src).unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .args(["emu", "-usm"])
+        .args(args)
+        .arg(&program)
+        .output()
+        .unwrap();
+    fs::remove_file(&program).unwrap();
+
+    out
+}
+
+fn assert_denied(out: &Output, denial: &str) {
+    assert_eq!(out.status.code(), Some(1));
+    assert!(
+        out.stdout.is_empty(),
+        "{}",
+        String::from_utf8_lossy(&out.stdout)
+    );
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(
+        stderr
+            .trim_end()
+            .ends_with(&format!("Заборонена Інструкція: {denial}")),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn denied_io_prints_nothing() {
+    let src = "клади 1\nдрук\n";
+    assert_eq!(
+        String::from_utf8_lossy(&run("io-allowed", src, &[]).stdout),
+        "1_зціл\n"
+    );
+    assert_denied(&run("io", src, &["--deny", "io"]), "\"друк\" за адресою 1");
+}
+
+#[test]
+fn denied_memory_is_not_written() {
+    // Without the denial the stored value would be printed back
+    let src = "клади 7\nклади 0\nзбережи\nклади 0\nзавантаж\nдрук\n";
+    assert_eq!(
+        String::from_utf8_lossy(&run("memory-allowed", src, &[]).stdout),
+        "7_зціл\n"
+    );
+    assert_denied(
+        &run("memory", src, &["--deny", "memory"]),
+        "\"збережи\" за адресою 2",
+    );
+}
+
+#[test]
+fn denied_nondeterminism_and_natives() {
+    let src = "випадк\nкинь\nклади 1\nдрук\n";
+    assert!(run("rand-allowed", src, &[]).status.success());
+    assert_denied(
+        &run("rand", src, &["--deny", "natives,nondeterminism"]),
+        "\"випадк\" за адресою 0",
+    );
+    assert_denied(
+        &run("natives", "клади 1\nсис 1\n", &["--deny", "natives"]),
+        "\"сис\" за адресою 1",
+    );
+}
+
+#[test]
+fn other_categories_stay_allowed() {
+    let out = run("other", "клади 1\nдрук\n", &["--deny", "memory,natives"]);
+    assert!(out.status.success());
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "1_зціл\n");
+}
+
+#[test]
+fn unknown_category_is_refused() {
+    let out = run("unknown", "клади 1\n", &["--deny", "io,bogus"]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("bogus"));
+}
@@ -0,0 +1,51 @@
+use std::{
+    env, fs,
+    process::{Command, Output},
+};
+
+// Three rounds of a loop with two mixed-type instructions and a same-type one
+const LOOP: &str = "клади 3_зціл
+цикл:
+клади 1_ціл
+клади 2_зціл
+сума
+кинь
+клади 0.5_дроб
+клади 1_зціл
+множ
+кинь
+клади 1_зціл
+різн
+копію 0
+крок_ненуль цикл
+";
+
+fn run(name: &str, args: &[&str]) -> Output {
+    let program = env::temp_dir().join(format!("uvm_coercion_{}_{name}.usm", std::process::id()));
+    fs::write(&program, LOOP).unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .args(["emu", "-usm"])
+        .args(args)
+        .arg(&program)
+        .output()
+        .unwrap();
+    fs::remove_file(&program).unwrap();
+    assert!(out.status.success());
+
+    out
+}
+
+#[test]
+fn mixed_types_warn_once_per_address() {
+    let out = run("warn", &["--warn-coercion"]);
+    assert_eq!(
+        String::from_utf8_lossy(&out.stderr),
+        "УВАГА: 3: \"сума\" перетворює 1_ціл на тип 2_зціл\n\
+         УВАГА: 7: \"множ\" перетворює 0.5_дроб на тип 1_зціл\n"
+    );
+}
+
+#[test]
+fn no_warnings_without_the_flag() {
+    assert!(run("quiet", &[]).stderr.is_empty());
+}
@@ -0,0 +1,31 @@
+use std::{env, fs, process::Command};
+
+fn stack_dump(name: &str, no_color: Option<&str>) -> String {
+    let program = env::temp_dir().join(format!("uvm_color_{}_{name}.usm", std::process::id()));
+    fs::write(&program, "клади 1\n").unwrap();
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_uvm"));
+    cmd.args(["emu", "-usm", "-ds", "--color"]).arg(&program);
+    match no_color {
+        Some(v) => cmd.env("NO_COLOR", v),
+        _ => cmd.env_remove("NO_COLOR"),
+    };
+    let out = cmd.output().unwrap();
+    fs::remove_file(&program).unwrap();
+    assert!(out.status.success());
+
+    String::from_utf8_lossy(&out.stdout).into_owned()
+}
+
+#[test]
+fn color_follows_the_flag() {
+    assert_eq!(stack_dump("on", None), "СТЕК [1] : \x1b[33m1_зціл\x1b[0m\n");
+    assert_eq!(
+        stack_dump("empty", Some("")),
+        "СТЕК [1] : \x1b[33m1_зціл\x1b[0m\n"
+    );
+}
+
+#[test]
+fn no_color_wins_over_the_flag() {
+    assert_eq!(stack_dump("off", Some("1")), "СТЕК [1] : 1_зціл\n");
+}
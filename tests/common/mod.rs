@@ -0,0 +1,102 @@
+// Shared by several test crates, each of them uses only a part of it
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+pub const INST_LIMIT: usize = 100_000;
+
+// Collects every `.usm` file under the `dir`, sorted so failures are reported in a stable order
+pub fn find_programs(dir: &Path) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir).unwrap_or_else(|e| panic!("{}: {e}", dir.display())) {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "usm") {
+                found.push(path);
+            }
+        }
+    }
+    found.sort();
+
+    found
+}
+
+// Runs the program and compares it with the expectation files next to it:
+//     <ІМ'Я>.out - everything the program prints
+//     <ІМ'Я>.stack - the final stack from the run summary, e.g. "[1] верхівка=42_зціл"
+// Without them the program only has to finish without an error.
+pub fn check_program(program: &Path) -> Result<(), String> {
+    let output = Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .args(["emu", "-usm", "-v", "-l", &INST_LIMIT.to_string()])
+        .arg(program)
+        .output()
+        .map_err(|e| format!("failed to run uvm: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "exited with {}:\n{}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (printed, summary) = match stdout.trim_end().rsplit_once('\n') {
+        Some((printed, summary)) => (format!("{printed}\n"), summary),
+        None => (String::new(), stdout.trim_end()),
+    };
+    let stack = summary
+        .split_once("Стек: ")
+        .map(|(_, stack)| stack)
+        .ok_or(format!("no run summary in the output:\n{stdout}"))?;
+
+    compare(&program.with_extension("out"), &printed)?;
+    compare(&program.with_extension("stack"), &format!("{stack}\n"))
+}
+
+// Translates the USM program into bytecode with the extra `dusm` options
+pub fn translate(program: &Path, output: &Path, opts: &[&str]) -> Result<(), String> {
+    let out = Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .arg("dusm")
+        .arg(program)
+        .args(opts)
+        .arg("-o")
+        .arg(output)
+        .output()
+        .map_err(|e| format!("failed to run uvm: {e}"))?;
+    if !out.status.success() {
+        return Err(String::from_utf8_lossy(&out.stderr).into_owned());
+    }
+
+    Ok(())
+}
+
+// Everything the bytecode program prints, including the run summary
+pub fn run_bytecode(program: &Path) -> Result<String, String> {
+    let out = Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .args(["emu", "-v", "-l", &INST_LIMIT.to_string()])
+        .arg(program)
+        .output()
+        .map_err(|e| format!("failed to run uvm: {e}"))?;
+    if !out.status.success() {
+        return Err(String::from_utf8_lossy(&out.stderr).into_owned());
+    }
+
+    Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+fn compare(expected: &Path, actual: &str) -> Result<(), String> {
+    match fs::read_to_string(expected) {
+        Ok(expected_text) if expected_text != actual => Err(format!(
+            "{} does not match:\n--- expected\n{expected_text}--- actual\n{actual}",
+            expected.display()
+        )),
+        _ => Ok(()),
+    }
+}
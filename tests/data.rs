@@ -0,0 +1,93 @@
+use std::{
+    env, fs,
+    process::{Command, Output},
+};
+
+fn uvm(name: &str, src: &str, args: &[&str]) -> Output {
+    let program = env::temp_dir().join(format!("uvm_data_{}_{name}.usm", std::process::id()));
+    fs::write(&program, src).unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .args(args)
+        .arg(&program)
+        .output()
+        .unwrap();
+    fs::remove_file(&program).unwrap();
+
+    out
+}
+
+#[test]
+fn data_survives_bytecode_round_trip() {
+    let tmp = env::temp_dir().join(format!("uvm_data_{}_round_trip", std::process::id()));
+    fs::create_dir_all(&tmp).unwrap();
+    fs::write(
+        tmp.join("1.usm"),
+        r#"дані привіт: "Привіт, \"світ\"!\n" "\t\\"
+        дані числа: 7_ціл 0.5_дроб -3
+        клади привіт
+        клади 18_ціл
+        друкр
+        клади числа
+        завантаж"#,
+    )
+    .unwrap();
+    let run = |args: &[&str]| {
+        let out = Command::new(env!("CARGO_BIN_EXE_uvm"))
+            .current_dir(&tmp)
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(
+            out.status.success(),
+            "{args:?}: {}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+        String::from_utf8_lossy(&out.stdout).into_owned()
+    };
+
+    run(&["dusm", "1.usm", "-o", "1.bin"]);
+    run(&["usm", "1.bin", "-o", "2.usm"]);
+    run(&["dusm", "2.usm", "-o", "2.bin"]);
+    assert_eq!(
+        fs::read_to_string(tmp.join("2.usm"))
+            .unwrap()
+            .lines()
+            .next(),
+        Some(r#"дані дані_програми: "Привіт, \"світ\"!\n\t\\" 7_ціл 0.5_дроб -3_зціл"#)
+    );
+    assert_eq!(
+        fs::read(tmp.join("1.bin")).unwrap(),
+        fs::read(tmp.join("2.bin")).unwrap()
+    );
+    assert_eq!(run(&["hash", "1.usm"]), run(&["hash", "1.bin"]));
+
+    let out = run(&["emu", "-v", "1.bin"]);
+    assert!(out.starts_with("Привіт, \"світ\"!\n\t\\"), "{out}");
+    assert!(out.ends_with("верхівка=7_ціл\n"), "{out}");
+    _ = fs::remove_dir_all(tmp);
+}
+
+#[test]
+fn string_data_is_printed_with_escapes() {
+    let src = r#"дані привіт: "Привіт, \"світ\"!\n" "\t\\"
+        клади привіт
+        клади 18_ціл
+        друкр"#;
+    let out = uvm("string", src, &["emu", "-usm"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&out.stdout),
+        "Привіт, \"світ\"!\n\t\\"
+    );
+}
+
+#[test]
+fn unclosed_string_is_an_error() {
+    let out = uvm("unclosed", "дані т: \"абв", &["emu", "-usm"]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("незакритий рядок у даних \"т\""));
+}
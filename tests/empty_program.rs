@@ -0,0 +1,51 @@
+use std::{
+    env, fs,
+    process::{Command, Output},
+};
+
+fn uvm(name: &str, src: &str, args: &[&str]) -> Output {
+    let path = env::temp_dir().join(format!("uvm-{name}-{}", std::process::id()));
+    fs::write(&path, src).unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .args(args)
+        .arg(&path)
+        .output()
+        .unwrap();
+    _ = fs::remove_file(path);
+
+    out
+}
+
+fn stderr(out: &Output) -> String {
+    String::from_utf8_lossy(&out.stderr).into_owned()
+}
+
+#[test]
+fn empty_bytecode_runs_with_a_notice() {
+    let out = uvm("empty-bin", "", &["emu", "-v"]);
+    assert!(out.status.success());
+    assert!(stderr(&out).contains("програма не містить інструкцій"));
+    assert!(String::from_utf8_lossy(&out.stdout).contains("Виконано: 0 інструкцій."));
+
+    let out = uvm("empty-bin-quiet", "", &["emu", "-q"]);
+    assert!(out.status.success());
+    assert!(stderr(&out).is_empty());
+}
+
+#[test]
+fn comments_only_source_is_empty() {
+    let out = uvm("comments", ";; нічого\n\n;; тут немає\n", &["emu", "-usm"]);
+    assert!(out.status.success());
+    assert!(stderr(&out).contains("програма не містить інструкцій"));
+}
+
+#[test]
+fn strict_empty_dump_fails() {
+    let out = uvm("empty-dump", "", &["dump"]);
+    assert!(out.status.success());
+    assert!(stderr(&out).contains("програма не містить інструкцій"));
+
+    let out = uvm("empty-dump-strict", "", &["dump", "--strict-empty"]);
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("Програма Не Містить Інструкцій"));
+}
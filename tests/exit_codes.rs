@@ -0,0 +1,62 @@
+use std::{env, fs, path::PathBuf, process::Command};
+
+fn exit_code(args: &[&str]) -> Option<i32> {
+    Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .args(args)
+        .output()
+        .unwrap()
+        .status
+        .code()
+}
+
+fn program(name: &str, src: &str) -> PathBuf {
+    let tmp = env::temp_dir().join(format!("uvm-exit-codes-{}", std::process::id()));
+    fs::create_dir_all(&tmp).unwrap();
+    let path = tmp.join(name);
+    fs::write(&path, src).unwrap();
+
+    path
+}
+
+#[test]
+fn missing_file_fails() {
+    assert_eq!(exit_code(&["emu", "/nonexistent/uvm/program.bin"]), Some(1));
+    assert_eq!(
+        exit_code(&["emu", "-usm", "/nonexistent/uvm/program.usm"]),
+        Some(1)
+    );
+    assert_eq!(
+        exit_code(&["dusm", "/nonexistent/uvm/program.usm"]),
+        Some(1)
+    );
+}
+
+#[test]
+fn wrong_arguments_fail() {
+    assert_eq!(exit_code(&["bogus", "program.usm"]), Some(1));
+    assert_eq!(exit_code(&["emu", "--bogus", "program.usm"]), Some(1));
+    assert_eq!(exit_code(&["emu", "-l", "many", "program.usm"]), Some(1));
+}
+
+#[test]
+fn parse_and_runtime_errors_fail() {
+    let parse = program("parse.usm", "клади\n");
+    let runtime = program("runtime.usm", "кинь\n");
+    assert_eq!(
+        exit_code(&["emu", "-usm", parse.to_str().unwrap()]),
+        Some(1)
+    );
+    assert_eq!(
+        exit_code(&["emu", "-usm", runtime.to_str().unwrap()]),
+        Some(1)
+    );
+}
+
+#[test]
+fn successful_run_and_usage_succeed() {
+    let ok = program("ok.usm", "клади 1\n");
+    assert_eq!(exit_code(&["emu", "-usm", ok.to_str().unwrap()]), Some(0));
+    assert_eq!(exit_code(&[]), Some(0));
+    assert_eq!(exit_code(&["doc-isa"]), Some(0));
+    _ = fs::remove_file(ok);
+}
@@ -0,0 +1,144 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+fn sandbox(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("uvm-file-io-{}-{name}", std::process::id()));
+    fs::create_dir_all(dir.join("root")).unwrap();
+
+    dir
+}
+
+fn run(dir: &Path, src: &str, args: &[&str]) -> Output {
+    let program = dir.join("program.usm");
+    fs::write(&program, src).unwrap();
+    Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .args(["emu", "-usm"])
+        .args(args)
+        .arg(&program)
+        .output()
+        .unwrap()
+}
+
+// Reads the file into the memory from the address 100 and prints it with its length
+fn read(path: &str) -> String {
+    format!(
+        "дані шлях: \"{path}\"
+        клади шлях
+        клади {len}_ціл
+        клади 100
+        сис файл_читати
+        копію 0
+        друк
+        клади 100
+        обмін
+        друкр",
+        len = path.chars().count()
+    )
+}
+
+#[test]
+fn files_are_read_and_written_inside_the_root() {
+    let dir = sandbox("allowed");
+    let root = dir.join("root");
+    fs::write(root.join("вхід.txt"), "42 ok").unwrap();
+    let root_arg = root.to_str().unwrap();
+
+    let out = run(
+        &dir,
+        &read("вхід.txt"),
+        &["--allow-fs", "--fs-root", root_arg],
+    );
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "5_ціл\n42 ok");
+
+    let write = "дані шлях: \"вихід.txt\"
+        дані текст: \"hi, uvm\"
+        клади шлях
+        клади 9_ціл
+        клади текст
+        клади 7_ціл
+        сис файл_писати
+        друк";
+    let out = run(&dir, write, &["--allow-fs", "--fs-root", root_arg]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "7_ціл\n");
+    assert_eq!(
+        fs::read_to_string(root.join("вихід.txt")).unwrap(),
+        "hi, uvm"
+    );
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn files_are_denied_without_the_flag() {
+    let dir = sandbox("denied");
+    let file = dir.join("root").join("вхід.txt");
+    fs::write(&file, "42").unwrap();
+
+    let out = run(&dir, &read(file.to_str().unwrap()), &[]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(out.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Доступ Заборонено: "));
+
+    // The root alone does not allow anything
+    let root = dir.join("root");
+    let out = run(
+        &dir,
+        &read("вхід.txt"),
+        &["--fs-root", root.to_str().unwrap()],
+    );
+    assert_eq!(out.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Доступ Заборонено: вхід.txt"));
+
+    // Without the root any path is allowed
+    let out = run(&dir, &read(file.to_str().unwrap()), &["--allow-fs"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "2_ціл\n42");
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn paths_can_not_escape_the_root() {
+    let dir = sandbox("escape");
+    fs::write(dir.join("таємниця.txt"), "secret").unwrap();
+    let root = dir.join("root");
+    let args = ["--allow-fs", "--fs-root", root.to_str().unwrap()];
+
+    let out = run(&dir, &read("../таємниця.txt"), &args);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(out.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Доступ Заборонено: ../таємниця.txt"));
+
+    let secret = dir.join("таємниця.txt");
+    let out = run(&dir, &read(secret.to_str().unwrap()), &args);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Доступ Заборонено: "));
+
+    // A new file outside of the root is refused before it is created
+    let write = "дані шлях: \"../новий.txt\"
+        клади шлях
+        клади 12_ціл
+        клади 0
+        клади 0_ціл
+        сис файл_писати";
+    let out = run(&dir, write, &args);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Доступ Заборонено: ../новий.txt"));
+    assert!(!dir.join("новий.txt").exists());
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,77 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+const TRICKY: &str = "клади 0.1_дроб\nклади 0.2_дроб\nсума\nклади 1e300_дроб\nклади -0.0_дроб\n\
+                      клади 5e-324_дроб\nклади 1.7976931348623157e308_дроб\n";
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("uvm-float-{name}-{}", std::process::id()));
+    _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("a.usm"), TRICKY).unwrap();
+
+    dir
+}
+
+fn uvm(dir: &Path, args: &[&str]) -> Output {
+    let out = Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "{args:?}: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    out
+}
+
+#[test]
+fn folded_sum_is_assembled_exactly() {
+    let dir = temp_dir("exact");
+    uvm(&dir, &["dusm", "a.usm", "--fold", "-o", "a.bin"]);
+    uvm(&dir, &["usm", "a.bin", "-o", "b.usm"]);
+    assert_eq!(
+        fs::read_to_string(dir.join("b.usm")).unwrap(),
+        "клади 0.30000000000000004_дроб\nклади 1e300_дроб\nклади -0.0_дроб\n\
+         клади 5e-324_дроб\nклади 1.7976931348623157e308_дроб\n"
+    );
+
+    uvm(&dir, &["dusm", "b.usm", "-o", "b.bin"]);
+    assert_eq!(
+        fs::read(dir.join("a.bin")).unwrap(),
+        fs::read(dir.join("b.bin")).unwrap()
+    );
+    _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn dump_rounds_to_the_precision() {
+    let dir = temp_dir("precision");
+    let out = uvm(&dir, &["dump", "-usm", "a.usm", "--float-precision", "3"]);
+    let dump = String::from_utf8_lossy(&out.stdout);
+    let lines = dump.lines().collect::<Vec<_>>();
+    assert_eq!(lines[..3], ["клади 0.100_дроб", "клади 0.200_дроб", "сума"]);
+    assert_eq!(lines[4..6], ["клади -0.000_дроб", "клади 0.000_дроб"]);
+    assert!(lines[3].ends_with(".000_дроб") && !lines[3].contains('e'));
+    _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn precision_is_refused_for_the_usm_output() {
+    let dir = temp_dir("refused");
+    uvm(&dir, &["dusm", "a.usm", "-o", "a.bin"]);
+    let out = Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .current_dir(&dir)
+        .args(["usm", "a.bin", "--float-precision", "2"])
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(1));
+    assert!(out.stdout.is_empty());
+    _ = fs::remove_dir_all(dir);
+}
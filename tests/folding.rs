@@ -0,0 +1,75 @@
+mod common;
+
+use std::{env, fs, path::Path, process::Command};
+
+// The stack and the output have to stay the same, only the instruction count may change
+fn without_count(output: &str) -> String {
+    output
+        .lines()
+        .map(|l| l.split_once("Стек: ").map_or(l, |(_, stack)| stack))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn folding_keeps_results() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/programs");
+    let tmp = env::temp_dir().join(format!("uvm-folding-{}", std::process::id()));
+    fs::create_dir_all(&tmp).unwrap();
+    let (plain, folded) = (tmp.join("plain.bin"), tmp.join("folded.bin"));
+
+    let mut changed = 0;
+    for program in common::find_programs(&dir) {
+        common::translate(&program, &plain, &[]).unwrap();
+        common::translate(&program, &folded, &["--fold"]).unwrap();
+        if fs::read(&plain).unwrap() != fs::read(&folded).unwrap() {
+            changed += 1;
+        }
+
+        let expected = common::run_bytecode(&plain).unwrap();
+        let actual = common::run_bytecode(&folded)
+            .unwrap_or_else(|e| panic!("{}: folded run failed: {e}", program.display()));
+        assert_eq!(
+            without_count(&expected),
+            without_count(&actual),
+            "{}",
+            program.display()
+        );
+    }
+    _ = fs::remove_dir_all(tmp);
+
+    assert!(changed > 0, "no program was folded");
+}
+
+#[test]
+fn jump_table_follows_folded_code() {
+    let program = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/programs/jump_table.usm");
+    let tmp = env::temp_dir().join(format!("uvm-folded-table-{}", std::process::id()));
+    fs::create_dir_all(&tmp).unwrap();
+    let folded = tmp.join("folded.bin");
+
+    common::translate(&program, &folded, &["--fold"]).unwrap();
+    let out = common::run_bytecode(&folded).unwrap();
+    assert!(out.ends_with("Стек: [1] верхівка=20_ціл\n"), "{out}");
+    _ = fs::remove_dir_all(tmp);
+}
+
+#[test]
+fn folding_error_points_at_the_source() {
+    let tmp = env::temp_dir().join(format!("uvm-folding-error-{}", std::process::id()));
+    fs::create_dir_all(&tmp).unwrap();
+    let program = tmp.join("div.usm");
+    fs::write(&program, ";; ділення на нуль\nклади 1\nклади 0\n  діли\n").unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .args(["dusm", "--fold"])
+        .arg(&program)
+        .arg("-o")
+        .arg(tmp.join("div.bin"))
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("4:3: помилка"), "{stderr}");
+    _ = fs::remove_dir_all(tmp);
+}
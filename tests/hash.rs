@@ -0,0 +1,71 @@
+use std::{env, fs, path::Path, process::Command};
+
+fn hash(file: &Path) -> String {
+    let out = Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .arg("hash")
+        .arg(file)
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let hash = String::from_utf8_lossy(&out.stdout).trim_end().to_string();
+    assert_eq!(hash.len(), 16, "{hash}");
+
+    hash
+}
+
+#[test]
+fn usm_and_bytecode_hash_the_same() {
+    let tmp = env::temp_dir().join(format!("uvm-hash-same-{}", std::process::id()));
+    fs::create_dir_all(&tmp).unwrap();
+    let (src, bin) = (tmp.join("a.usm"), tmp.join("a.bin"));
+    // Comments, layout and labels are not a part of the program
+    fs::write(
+        &src,
+        ";; сума\n  клади 2\nпочаток: клади 3_ціл ;; друге\nсума\n",
+    )
+    .unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .arg("dusm")
+        .arg(&src)
+        .arg("-o")
+        .arg(&bin)
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+
+    assert_eq!(hash(&src), hash(&bin));
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sum.usm");
+    assert_eq!(hash(&src), hash(&fixture));
+    _ = fs::remove_dir_all(tmp);
+}
+
+#[test]
+fn different_programs_hash_differently() {
+    let tmp = env::temp_dir().join(format!("uvm-hash-differ-{}", std::process::id()));
+    fs::create_dir_all(&tmp).unwrap();
+    let programs = [
+        "клади 2\nклади 3_ціл\nсума\n",
+        "клади 3_ціл\nклади 2\nсума\n",
+        "клади 2_ціл\nклади 3_ціл\nсума\n",
+        "клади 2\nклади 3_ціл\nсума?\n",
+        "клади 2\nклади 3_ціл\nрізн\n",
+        "клади 2\nклади 3_ціл\n",
+    ];
+    let hashes = programs
+        .iter()
+        .enumerate()
+        .map(|(n, src)| {
+            let file = tmp.join(format!("{n}.usm"));
+            fs::write(&file, src).unwrap();
+            hash(&file)
+        })
+        .collect::<Vec<_>>();
+    for (n, h) in hashes.iter().enumerate() {
+        assert!(!hashes[n + 1..].contains(h), "{:?}", programs[n]);
+    }
+    _ = fs::remove_dir_all(tmp);
+}
@@ -0,0 +1,132 @@
+mod common;
+
+use std::{env, fs, path::Path};
+
+// The stack and the output have to stay the same, only the instruction count may change
+fn without_count(output: &str) -> String {
+    output
+        .lines()
+        .map(|l| l.split_once("Стек: ").map_or(l, |(_, stack)| stack))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn inlining_keeps_results() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/programs");
+    let tmp = env::temp_dir().join(format!("uvm-inlining-{}", std::process::id()));
+    fs::create_dir_all(&tmp).unwrap();
+    let (plain, inlined) = (tmp.join("plain.bin"), tmp.join("inlined.bin"));
+
+    let mut changed = 0;
+    for program in common::find_programs(&dir) {
+        common::translate(&program, &plain, &[]).unwrap();
+        common::translate(&program, &inlined, &["--inline-threshold", "16"]).unwrap();
+        if fs::read(&plain).unwrap() != fs::read(&inlined).unwrap() {
+            changed += 1;
+        }
+
+        let expected = common::run_bytecode(&plain).unwrap();
+        let actual = common::run_bytecode(&inlined)
+            .unwrap_or_else(|e| panic!("{}: inlined run failed: {e}", program.display()));
+        assert_eq!(
+            without_count(&expected),
+            without_count(&actual),
+            "{}",
+            program.display()
+        );
+    }
+    _ = fs::remove_dir_all(tmp);
+
+    assert!(changed > 0, "no program was inlined");
+}
+
+#[test]
+fn recursive_routine_is_not_inlined() {
+    let program = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/programs/recursion.usm");
+    let tmp = env::temp_dir().join(format!("uvm-recursion-{}", std::process::id()));
+    fs::create_dir_all(&tmp).unwrap();
+    let (plain, inlined) = (tmp.join("plain.bin"), tmp.join("inlined.bin"));
+
+    common::translate(&program, &plain, &[]).unwrap();
+    common::translate(&program, &inlined, &["--inline-threshold", "16"]).unwrap();
+    assert_eq!(fs::read(&plain).unwrap(), fs::read(&inlined).unwrap());
+    _ = fs::remove_dir_all(tmp);
+}
+
+#[test]
+fn relative_jumps_survive_folding_and_inlining() {
+    let program = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/programs/relative_jump.usm");
+    let tmp = env::temp_dir().join(format!("uvm-relative-{}", std::process::id()));
+    fs::create_dir_all(&tmp).unwrap();
+    let (plain, optimized) = (tmp.join("plain.bin"), tmp.join("optimized.bin"));
+
+    common::translate(&program, &plain, &[]).unwrap();
+    common::translate(
+        &program,
+        &optimized,
+        &["--fold", "--inline-threshold", "16"],
+    )
+    .unwrap();
+    assert_ne!(fs::read(&optimized).unwrap(), fs::read(&plain).unwrap());
+    assert_eq!(
+        without_count(&common::run_bytecode(&plain).unwrap()),
+        without_count(&common::run_bytecode(&optimized).unwrap())
+    );
+    _ = fs::remove_dir_all(tmp);
+}
+
+#[test]
+fn jump_table_follows_inlined_call() {
+    let program =
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/programs/call_before_table.usm");
+    let tmp = env::temp_dir().join(format!("uvm-inlined-table-{}", std::process::id()));
+    fs::create_dir_all(&tmp).unwrap();
+    let (plain, inlined) = (tmp.join("plain.bin"), tmp.join("inlined.bin"));
+
+    common::translate(&program, &plain, &[]).unwrap();
+    common::translate(&program, &inlined, &["--inline-threshold", "4"]).unwrap();
+    assert_ne!(fs::read(&plain).unwrap(), fs::read(&inlined).unwrap());
+    let out = common::run_bytecode(&inlined).unwrap();
+    assert!(out.ends_with("Стек: [1] верхівка=20_ціл\n"), "{out}");
+    _ = fs::remove_dir_all(tmp);
+}
+
+#[test]
+fn routine_with_jump_table_is_not_inlined() {
+    let tmp = env::temp_dir().join(format!("uvm-table-routine-{}", std::process::id()));
+    fs::create_dir_all(&tmp).unwrap();
+    let program = tmp.join("table.usm");
+    fs::write(
+        &program,
+        "клади 0_ціл\nвиклик ф\nкінчай\nф:\nтаблиця а\nа:\nповернись\n",
+    )
+    .unwrap();
+    let (plain, inlined) = (tmp.join("plain.bin"), tmp.join("inlined.bin"));
+
+    common::translate(&program, &plain, &[]).unwrap();
+    common::translate(&program, &inlined, &["--inline-threshold", "16"]).unwrap();
+    assert_eq!(fs::read(&plain).unwrap(), fs::read(&inlined).unwrap());
+    _ = fs::remove_dir_all(tmp);
+}
+
+#[test]
+fn many_calls_grow_the_program() {
+    let tmp = env::temp_dir().join(format!("uvm-many-calls-{}", std::process::id()));
+    fs::create_dir_all(&tmp).unwrap();
+    let program = tmp.join("calls.usm");
+    let mut src = "клади 0\n".to_string();
+    src.push_str(&"клич ф\n".repeat(300));
+    src.push_str(
+        "кінчай\nф:\nміняй 1\nклади 1\nсума\nклади 0\nсума\nклади 1\nмнож\nміняй 1\nвертай\n",
+    );
+    fs::write(&program, src).unwrap();
+    let (plain, inlined) = (tmp.join("plain.bin"), tmp.join("inlined.bin"));
+
+    common::translate(&program, &plain, &[]).unwrap();
+    common::translate(&program, &inlined, &["--inline-threshold", "8"]).unwrap();
+    assert!(fs::read(&inlined).unwrap().len() > fs::read(&plain).unwrap().len());
+    let out = common::run_bytecode(&inlined).unwrap();
+    assert!(out.ends_with("Стек: [1] верхівка=300_зціл\n"), "{out}");
+    _ = fs::remove_dir_all(tmp);
+}
@@ -0,0 +1,89 @@
+use std::{
+    env, fs,
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+fn run_with_input(name: &str, src: &str, input: &str) -> Output {
+    let program = env::temp_dir().join(format!("uvm_input_{}_{name}.usm", std::process::id()));
+    fs::write(&program, src).unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .args(["emu", "-usm", "-v"])
+        .arg(&program)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let out = child.wait_with_output().unwrap();
+    fs::remove_file(&program).unwrap();
+
+    out
+}
+
+#[test]
+fn read_pushes_numbers_from_the_input() {
+    let out = run_with_input("sum", "читай\nчитай\nсума", "2 3");
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert!(String::from_utf8_lossy(&out.stdout).ends_with("Стек: [1] верхівка=5_зціл\n"));
+}
+
+#[test]
+fn read_keeps_the_type_suffix() {
+    let out = run_with_input("suffix", "читай\nчитай", "\n 1.5_дроб\t7_ціл\n");
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert!(String::from_utf8_lossy(&out.stdout).ends_with("Стек: [2] верхівка=7_ціл\n"));
+}
+
+#[test]
+fn read_fails_on_a_token_that_is_not_a_number() {
+    let out = run_with_input("invalid", "читай", "два");
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Помилка Вводу: \"два\" не є числом"));
+}
+
+#[test]
+fn read_char_loops_until_the_end_of_the_input() {
+    let src = "луп:
+        читайз
+        копію 0
+        клади 0
+        менше
+        крок_ненуль кінець
+        друкз
+        крок луп
+    кінець:
+        кинь";
+    let out = run_with_input("chars", src, "ok, їжак\n");
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert!(String::from_utf8_lossy(&out.stdout).starts_with("ok, їжак\nВиконано:"));
+}
+
+#[test]
+fn read_takes_a_typed_line_for_the_next_instruction() {
+    let out = run_with_input("line", "клади 10\nчитай\nсума", "5_ціл\n");
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert!(String::from_utf8_lossy(&out.stdout).ends_with("Стек: [1] верхівка=15_ціл\n"));
+}
@@ -0,0 +1,34 @@
+use std::{env, fmt::Write, fs, process::Command, time::Instant};
+
+const LABELS: usize = 10_000;
+
+// Jumps from the last label down to the first one, every label adds 1. Labels
+// like "л1", "л10" and "л100" share prefixes, so a lookup that is not an exact
+// match lands on the wrong label and breaks the count.
+#[test]
+fn thousands_of_labels_jump_to_exact_targets() {
+    let mut src = format!("клади 0\nкрок л{}\n", LABELS - 1);
+    for n in 1..LABELS {
+        writeln!(src, "л{n}: клади 1\n    сума\n    крок л{}", n - 1).unwrap();
+    }
+    src.push_str("л0: кінчай\n");
+
+    let program = env::temp_dir().join(format!("uvm_labels_{}.usm", std::process::id()));
+    fs::write(&program, src).unwrap();
+    let started = Instant::now();
+    let out = Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .args(["emu", "-usm", "-v"])
+        .arg(&program)
+        .output()
+        .unwrap();
+    fs::remove_file(&program).unwrap();
+
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert!(String::from_utf8_lossy(&out.stdout)
+        .ends_with(&format!("Стек: [1] верхівка={}_зціл\n", LABELS - 1)));
+    assert!(started.elapsed().as_secs() < 10);
+}
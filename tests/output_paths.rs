@@ -0,0 +1,91 @@
+use std::{
+    env, fs,
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("uvm-{name}-{}", std::process::id()));
+    _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("a.usm"), "клади 1\nклади 2\nсума\n").unwrap();
+
+    dir
+}
+
+fn uvm(dir: &PathBuf, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn same_path_is_refused() {
+    let dir = temp_dir("same-path");
+    let out = uvm(&dir, &["dusm", "a.usm", "-o", "a.usm"]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Збігається З Вхідним"));
+    assert_eq!(
+        fs::read_to_string(dir.join("a.usm")).unwrap(),
+        "клади 1\nклади 2\nсума\n"
+    );
+    _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn differently_spelled_same_path_is_refused() {
+    let dir = temp_dir("spelled-path");
+    assert!(uvm(&dir, &["dusm", "a.usm", "-o", "a.bin"])
+        .status
+        .success());
+    let bytecode = fs::read(dir.join("a.bin")).unwrap();
+
+    for args in [
+        ["usm", "a.bin", "-o", "./a.bin"],
+        ["dump", "./a.bin", "-o", "a.bin"],
+        ["patch", "a.bin", "-o", "../{dir}/a.bin"],
+    ] {
+        let args = args.map(|a| a.replace("{dir}", &dir.file_name().unwrap().to_string_lossy()));
+        let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+        let out = uvm(&dir, &args);
+        assert!(!out.status.success(), "{args:?} succeeded");
+    }
+    assert_eq!(fs::read(dir.join("a.bin")).unwrap(), bytecode);
+    _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn distinct_paths_are_written() {
+    let dir = temp_dir("distinct-path");
+    assert!(uvm(&dir, &["dusm", "a.usm", "-o", "a.bin"])
+        .status
+        .success());
+    assert!(uvm(&dir, &["usm", "a.bin", "-o", "b.usm"]).status.success());
+    assert_eq!(
+        fs::read_to_string(dir.join("b.usm")).unwrap(),
+        "клади 1_зціл\nклади 2_зціл\nсума\n"
+    );
+    _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn dump_is_written_to_the_output_file() {
+    let dir = temp_dir("dump-output");
+    assert!(uvm(&dir, &["dusm", "a.usm", "-o", "a.bin"])
+        .status
+        .success());
+
+    let printed = uvm(&dir, &["dump", "a.bin"]);
+    assert!(printed.status.success());
+    let written = uvm(&dir, &["dump", "a.bin", "-o", "a.txt"]);
+    assert!(written.status.success());
+    assert!(written.stdout.is_empty());
+    assert_eq!(fs::read(dir.join("a.txt")).unwrap(), printed.stdout);
+    assert_eq!(
+        fs::read_to_string(dir.join("a.txt")).unwrap(),
+        "клади 1_зціл\nклади 2_зціл\nсума\n"
+    );
+    _ = fs::remove_dir_all(dir);
+}
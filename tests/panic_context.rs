@@ -0,0 +1,40 @@
+use std::{env, fs, path::Path, process::Command};
+
+const PROGRAM: &str = "клади 1\n\n    сума   ;; другого доданка немає\nкінчай\n";
+
+fn stderr(args: &[&str], program: &Path) -> String {
+    let out = Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .args(args)
+        .arg(program)
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(1));
+
+    String::from_utf8_lossy(&out.stderr).into_owned()
+}
+
+#[test]
+fn context_shows_the_source_line_of_usm() {
+    let tmp = env::temp_dir().join(format!("uvm-panic-context-{}", std::process::id()));
+    fs::create_dir_all(&tmp).unwrap();
+    let usm = tmp.join("sum.usm");
+    fs::write(&usm, PROGRAM).unwrap();
+
+    let ctx = stderr(&["emu", "-usm"], &usm);
+    assert!(ctx.contains("-> 1: сума    <- Незаповненість Стека\n"));
+    assert!(ctx.contains("\nРЯДОК 3: сума   ;; другого доданка немає\n"));
+
+    // The bytecode has no source to point into
+    let bin = tmp.join("sum.bin");
+    let out = Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .arg("dusm")
+        .arg(&usm)
+        .arg("-o")
+        .arg(&bin)
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+    let ctx = stderr(&["emu"], &bin);
+    assert!(ctx.contains("-> 1: сума    <- Незаповненість Стека\n"));
+    assert!(!ctx.contains("РЯДОК"));
+}
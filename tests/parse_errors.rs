@@ -0,0 +1,51 @@
+use std::{env, fs, process::Command};
+
+fn parse_error(name: &str, src: &str) -> String {
+    let path = env::temp_dir().join(format!("uvm-{name}-{}.usm", std::process::id()));
+    fs::write(&path, src).unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .arg("emu")
+        .arg(&path)
+        .output()
+        .unwrap();
+    _ = fs::remove_file(path);
+    assert!(!out.status.success());
+
+    String::from_utf8_lossy(&out.stderr).trim_end().to_string()
+}
+
+#[test]
+fn errors_point_at_the_token() {
+    assert_eq!(
+        parse_error("unknown-label", ";; коментар\nклади 1\n    крок нема ;; тут\n"),
+        "Помилка Перекладу: 3:10: спроба використати неіснуючий лейбл \"нема\" для інструкції \"крок\""
+    );
+    assert_eq!(
+        parse_error("unexpected-operand", "клади 1 кинь 5\n"),
+        "Помилка Перекладу: 1:14: не передбачений операнд \"5_зціл\" для інструкції \"кинь\""
+    );
+}
+
+#[test]
+fn missing_operand_points_at_the_instruction() {
+    assert_eq!(
+        parse_error("missing-operand", "клади 1\r\n  крок\r\n"),
+        "Помилка Перекладу: 2:3: відсутнє значення для інструкції \"крок\""
+    );
+}
+
+#[test]
+fn data_needs_a_name_and_values() {
+    assert_eq!(
+        parse_error("data-no-values", "дані порожні:\n"),
+        "Помилка Перекладу: 1:1: відсутні значення для даних \"порожні\""
+    );
+    assert_eq!(
+        parse_error("data-label", "дані т: 1 два\n"),
+        "Помилка Перекладу: 1:1: \"два\" не є значенням для даних \"т\""
+    );
+    assert_eq!(
+        parse_error("data-jump", "дані т: 1\nкрок т\n"),
+        "Помилка Перекладу: 2:6: спроба використати неіснуючий лейбл \"т\" для інструкції \"крок\""
+    );
+}
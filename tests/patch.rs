@@ -0,0 +1,105 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+// Copy of the `sum` fixture: клади 2, клади 3_ціл, сума
+fn fixture(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("uvm-patch-{name}-{}", std::process::id()));
+    _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("sum.bin");
+    fs::copy(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sum.bin"),
+        &path,
+    )
+    .unwrap();
+
+    path
+}
+
+fn uvm(args: &[&str], file: &Path) -> Output {
+    let (sub, opts) = args.split_first().unwrap();
+    Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .arg(sub)
+        .arg(file)
+        .args(opts)
+        .output()
+        .unwrap()
+}
+
+fn dump(file: &Path) -> Vec<String> {
+    let out = uvm(&["dump"], file);
+    assert!(out.status.success());
+    String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .map(String::from)
+        .collect()
+}
+
+const ORIGINAL: [&str; 3] = ["клади 2_зціл", "клади 3_ціл", "сума"];
+
+#[test]
+fn set_replaces_only_the_target() {
+    let file = fixture("set");
+    let out = uvm(&["patch", "--set", "1", "клади 7_ціл"], &file);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert_eq!(dump(&file), ["клади 2_зціл", "клади 7_ціл", "сума"]);
+    _ = fs::remove_dir_all(file.parent().unwrap());
+}
+
+#[test]
+fn nop_blanks_the_range() {
+    let file = fixture("nop");
+    let patched = file.with_file_name("patched.bin");
+    let out = uvm(
+        &["patch", "--nop", "0..1", "-o", patched.to_str().unwrap()],
+        &file,
+    );
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert_eq!(dump(&patched), ["неоп", "неоп", "сума"]);
+    assert_eq!(dump(&file), ORIGINAL);
+    _ = fs::remove_dir_all(file.parent().unwrap());
+}
+
+#[test]
+fn failed_patches_write_nothing() {
+    let file = fixture("refused");
+    let bytes = fs::read(&file).unwrap();
+    let refused: [&[&str]; 5] = [
+        &["patch", "--set", "3", "неоп"],
+        &["patch", "--set", "0", "крок 3"],
+        &["patch", "--set", "0", "клади"],
+        &["patch", "--set", "0", "нема 1"],
+        &["patch", "--nop", "2..3"],
+    ];
+    for args in refused {
+        let out = uvm(args, &file);
+        assert_eq!(out.status.code(), Some(1), "{args:?}");
+        assert_eq!(fs::read(&file).unwrap(), bytes, "{args:?}");
+    }
+    assert_eq!(dump(&file), ORIGINAL);
+    _ = fs::remove_dir_all(file.parent().unwrap());
+}
+
+#[test]
+fn branch_inside_the_program_is_accepted() {
+    let file = fixture("branch");
+    let out = uvm(&["patch", "--set", "0", "крок 2"], &file);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert_eq!(dump(&file)[0], "крок 2_зціл");
+    _ = fs::remove_dir_all(file.parent().unwrap());
+}
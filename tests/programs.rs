@@ -0,0 +1,51 @@
+mod common;
+
+use std::{env, path::Path, process::Command};
+
+#[test]
+fn programs() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/programs");
+    let programs = common::find_programs(&dir);
+    assert!(!programs.is_empty(), "no programs in {}", dir.display());
+
+    let failures = programs
+        .iter()
+        .filter_map(|p| {
+            common::check_program(p)
+                .err()
+                .map(|e| format!("{}: {e}", p.display()))
+        })
+        .collect::<Vec<_>>();
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+fn run_fixture(name: &str) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .args(["emu", "-usm", "-v"])
+        .arg(
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/fixtures")
+                .join(name),
+        )
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn passed_assertion_lets_the_program_go_on() {
+    let out = run_fixture("check_passes.usm");
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert!(String::from_utf8_lossy(&out.stdout).ends_with("Стек: [1] верхівка=4_зціл\n"));
+}
+
+#[test]
+fn failed_assertion_exits_with_an_error() {
+    let out = run_fixture("check_fails.usm");
+    assert_eq!(out.status.code(), Some(1));
+    assert!(out.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&out.stderr).ends_with("Перевірку Не Пройдено: за адресою 6\n"));
+}
@@ -0,0 +1,41 @@
+use std::{env, fs, process::Command};
+
+// Prints five numbers from the generator
+const SRC: &str = "клади 5
+луп:
+    випадк
+    друк
+    клади 1
+    різн
+    копію 0
+    крок_ненуль луп";
+
+fn random_sequence(seed: &str) -> String {
+    let program = env::temp_dir().join(format!("uvm_random_{}_{seed}.usm", std::process::id()));
+    fs::write(&program, SRC).unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .args(["emu", "-usm", "--seed", seed])
+        .arg(&program)
+        .output()
+        .unwrap();
+    fs::remove_file(&program).unwrap();
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    String::from_utf8(out.stdout).unwrap()
+}
+
+#[test]
+fn same_seed_gives_the_same_sequence() {
+    let first = random_sequence("42");
+    assert_eq!(first.lines().count(), 5);
+    assert_eq!(first, random_sequence("42"));
+}
+
+#[test]
+fn different_seeds_give_different_sequences() {
+    assert_ne!(random_sequence("1"), random_sequence("2"));
+}
@@ -0,0 +1,33 @@
+mod common;
+
+use std::{env, fs, path::Path, process::Command};
+
+// Bytecode translated back into USM has to translate into the same bytecode
+#[test]
+fn programs_survive_usm_round_trip() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/programs");
+    let tmp = env::temp_dir().join(format!("uvm-round-trip-{}", std::process::id()));
+    fs::create_dir_all(&tmp).unwrap();
+    let (first, text, second) = (tmp.join("1.bin"), tmp.join("1.usm"), tmp.join("2.bin"));
+
+    for program in common::find_programs(&dir) {
+        common::translate(&program, &first, &[]).unwrap();
+        let out = Command::new(env!("CARGO_BIN_EXE_uvm"))
+            .arg("usm")
+            .arg(&first)
+            .arg("-o")
+            .arg(&text)
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "{}", program.display());
+        common::translate(&text, &second, &[]).unwrap();
+
+        assert_eq!(
+            fs::read(&first).unwrap(),
+            fs::read(&second).unwrap(),
+            "{}",
+            program.display()
+        );
+    }
+    _ = fs::remove_dir_all(tmp);
+}
@@ -0,0 +1,52 @@
+use std::{
+    env, fs,
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+fn run_source(name: &str, src: &[u8]) -> Output {
+    let path: PathBuf = env::temp_dir().join(format!("uvm-{name}-{}.usm", std::process::id()));
+    fs::write(&path, src).unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .args(["emu", "-v"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    _ = fs::remove_file(path);
+
+    out
+}
+
+fn stdout(out: &Output) -> String {
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    String::from_utf8_lossy(&out.stdout).into_owned()
+}
+
+#[test]
+fn leading_bom_is_skipped() {
+    let out = run_source("bom", "\u{feff}клади 1\nклади 2\nсума\n".as_bytes());
+    assert!(stdout(&out).contains("Стек: [1] верхівка=3_зціл"));
+}
+
+#[test]
+fn crlf_line_endings_are_accepted() {
+    let out = run_source(
+        "crlf",
+        "клади 3_ціл\r\nлуп:\r\n    клади 1_ціл\r\n    різн\r\n    копію 0\r\n    крок? луп\r\n"
+            .as_bytes(),
+    );
+    assert!(stdout(&out).contains("Стек: [1] верхівка=0_ціл"));
+}
+
+#[test]
+fn invalid_utf8_reports_the_offset() {
+    let mut src = "клади 1\n".as_bytes().to_vec();
+    src.extend_from_slice(b"\xff\n");
+    let out = run_source("invalid-utf8", &src);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("байт 13 не є UTF-8"));
+}
@@ -0,0 +1,802 @@
+use std::{env, fs, process::Command};
+
+// Executable specification of the instruction set. Every case pushes its
+// initial stack, runs the instruction source and checks the whole stack at
+// the end of the program (a snapshot at the `кінець_специфікації` label)
+// or the error the run stopped with.
+enum Expect {
+    Stack(&'static str),
+    Panic(&'static str),
+    // The program stops before reaching its end
+    Halt,
+}
+
+use Expect::*;
+
+// Fills the stack to its capacity of 1024 values
+const FULL: &str = "ПОВНИЙ";
+
+struct Case {
+    stack: &'static [&'static str],
+    src: &'static str,
+    expect: Expect,
+}
+
+const fn case(stack: &'static [&'static str], src: &'static str, expect: Expect) -> Case {
+    Case { stack, src, expect }
+}
+
+const OVERFLOW: Expect = Panic("Переповнений Стек");
+const UNDERFLOW: Expect = Panic("Незаповненість Стека");
+const VALUE_OVERFLOW: Expect = Panic("Перевищено Ліміт Значення");
+const NO_FRAME: Expect = Panic("Немає Відкритого Кадру");
+
+#[rustfmt::skip]
+const SPEC: &[Case] = &[
+    // неоп
+    case(&[], "неоп", Stack("")),
+    case(&["1"], "неоп", Stack("1_зціл")),
+    case(&["1", "0"], "неоп?", Stack("1_зціл")),
+    case(&[], "неоп?", UNDERFLOW),
+    // The condition is popped whether the instruction runs or not
+    case(&["1", "2", "0.5_дроб"], "сума?", Stack("3_зціл")),
+    case(&["1", "2", "-1"], "сума?", Stack("3_зціл")),
+    case(&["1", "2", "0"], "сума?", Stack("1_зціл 2_зціл")),
+    case(&["1", "2", "0.0"], "сума?", Stack("1_зціл 2_зціл")),
+    case(&[], "сума?", UNDERFLOW),
+
+    // клади
+    case(&[], "клади 5", Stack("5_зціл")),
+    case(&[], "клади 5_ціл", Stack("5_ціл")),
+    case(&[], "клади 2.5", Stack("2.5_дроб")),
+    case(&[], "клади -3", Stack("-3_зціл")),
+    case(&["0"], "клади? 5", Stack("")),
+    case(&["0.5"], "клади? 5", Stack("5_зціл")),
+    case(&["-1"], "клади? 5", Stack("5_зціл")),
+    case(&[], "клади? 5", UNDERFLOW),
+    case(&[FULL], "клади 5", OVERFLOW),
+
+    // копію
+    case(&["1", "2"], "копію 1", Stack("1_зціл 2_зціл 1_зціл")),
+    case(&["1", "2"], "копію 0", Stack("1_зціл 2_зціл 2_зціл")),
+    case(&["1", "2"], "копію 2", UNDERFLOW),
+    case(&["7", "8", "1"], "копію", Stack("7_зціл 8_зціл 7_зціл")),
+    case(&["7", "8", "2"], "копію", Panic("Неприпустиме Значення Операнда: 2_зціл")),
+    case(&["7", "0.5"], "копію", Panic("Неприпустиме Значення Операнда: 0.5_дроб")),
+    case(&[], "копію", UNDERFLOW),
+    case(&[FULL], "копію 0", OVERFLOW),
+
+    // кинь
+    case(&["1", "2"], "кинь", Stack("1_зціл")),
+    case(&["1", "2", "0"], "кинь?", Stack("1_зціл 2_зціл")),
+    case(&[], "кинь", UNDERFLOW),
+
+    // кинь_де
+    case(&["1", "2", "3"], "кинь_де 0", Stack("1_зціл 2_зціл")),
+    case(&["1", "2.5", "3_ціл"], "кинь_де 1", Stack("1_зціл 3_ціл")),
+    case(&["1", "2", "3", "4"], "кинь_де 2", Stack("1_зціл 3_зціл 4_зціл")),
+    case(&["1", "2", "3"], "кинь_де 2", Stack("2_зціл 3_зціл")),
+    case(&["1", "2"], "кинь_де 2", Panic("Неприпустиме Значення Операнда: 2_зціл")),
+    case(&[], "кинь_де 0", Panic("Неприпустиме Значення Операнда: 0_зціл")),
+
+    // рівн
+    case(&["3", "3"], "рівн", Stack("3_зціл 3_зціл 1_ціл")),
+    case(&["3", "4"], "рівн", Stack("3_зціл 4_зціл 0_ціл")),
+    case(&["3", "3_ціл"], "рівн", Stack("3_зціл 3_ціл 0_ціл")),
+    case(&["1.5", "1.5"], "рівн", Stack("1.5_дроб 1.5_дроб 1_ціл")),
+    case(&["NaN", "NaN"], "рівн", Stack("NaN_дроб NaN_дроб 0_ціл")),
+    case(&["3"], "рівн", UNDERFLOW),
+
+    // нерівн
+    case(&["3", "3"], "нерівн", Stack("3_зціл 3_зціл 0_ціл")),
+    case(&["3", "4"], "нерівн", Stack("3_зціл 4_зціл 1_ціл")),
+    case(&["3_ціл", "4_ціл"], "нерівн", Stack("3_ціл 4_ціл 1_ціл")),
+    case(&["1.5", "1.5"], "нерівн", Stack("1.5_дроб 1.5_дроб 0_ціл")),
+    // NaN is not equal even to itself
+    case(&["NaN", "NaN"], "нерівн", Stack("NaN_дроб NaN_дроб 1_ціл")),
+    case(&["3"], "нерівн", UNDERFLOW),
+
+    // крок
+    case(&["1"], "крок кінець_специфікації\nклади 9", Stack("1_зціл")),
+    case(&["1", "0"], "крок? кінець_специфікації\nклади 9", Stack("1_зціл 9_зціл")),
+    case(&["1", "1"], "крок? кінець_специфікації\nклади 9", Stack("1_зціл")),
+
+    // крок_нуль
+    case(&["1", "0"], "крок_нуль кінець_специфікації\nклади 9", Stack("1_зціл")),
+    case(&["1", "0.0"], "крок_нуль кінець_специфікації\nклади 9", Stack("1_зціл")),
+    case(&["1", "2"], "крок_нуль кінець_специфікації\nклади 9", Stack("1_зціл 9_зціл")),
+    case(&[], "крок_нуль кінець_специфікації", UNDERFLOW),
+
+    // крок_ненуль
+    case(&["1", "2"], "крок_ненуль кінець_специфікації\nклади 9", Stack("1_зціл")),
+    case(&["1", "-1"], "крок_ненуль кінець_специфікації\nклади 9", Stack("1_зціл")),
+    case(&["1", "0_ціл"], "крок_ненуль кінець_специфікації\nклади 9", Stack("1_зціл 9_зціл")),
+    case(&[], "крок_ненуль кінець_специфікації", UNDERFLOW),
+
+    // крок_від
+    case(&["1"], "крок_від 2\nклади 9", Stack("1_зціл")),
+    case(&["1"], "крок_від @кінець_специфікації\nклади 9", Stack("1_зціл")),
+    case(&["3"], "луп: клади 1\nрізн\nкопію 0\nкрок_нуль кінець_специфікації\nкрок_від -4", Stack("0_зціл")),
+    case(&["3"], "луп: клади 1\nрізн\nкопію 0\nкрок_нуль кінець_специфікації\nкрок_від @луп", Stack("0_зціл")),
+    case(&["1", "0"], "крок_від? 2\nклади 9", Stack("1_зціл 9_зціл")),
+    case(&["1"], "крок_від -5", Panic("Неприпустиме Значення Операнда: -5_зціл")),
+    case(&["1"], "крок_від 100", Panic("Неприпустиме Значення Операнда: 100_зціл")),
+
+    // сума
+    case(&["2", "3"], "сума", Stack("5_зціл")),
+    case(&["2_ціл", "3_ціл"], "сума", Stack("5_ціл")),
+    case(&["0.5", "0.25"], "сума", Stack("0.75_дроб")),
+    // The next value takes the type of the top one
+    case(&["2", "0.5"], "сума", Stack("2.5_дроб")),
+    case(&["2.5", "1_ціл"], "сума", Stack("3_ціл")),
+    case(&["18446744073709551615_ціл", "1_ціл"], "сума", VALUE_OVERFLOW),
+    case(&["1", "2", "0"], "сума?", Stack("1_зціл 2_зціл")),
+    case(&["1"], "сума", UNDERFLOW),
+
+    // різн
+    case(&["10", "3"], "різн", Stack("7_зціл")),
+    case(&["3", "10"], "різн", Stack("-7_зціл")),
+    case(&["3_ціл", "10_ціл"], "різн", VALUE_OVERFLOW),
+    case(&["1.5", "1.5"], "різн", Stack("0.0_дроб")),
+    case(&[], "різн", UNDERFLOW),
+
+    // множ
+    case(&["6", "7"], "множ", Stack("42_зціл")),
+    case(&["1.5", "2.0"], "множ", Stack("3.0_дроб")),
+    // A negative value loses its sign when it becomes unsigned
+    case(&["-4", "2_ціл"], "множ", Stack("8_ціл")),
+    case(&["1"], "множ", UNDERFLOW),
+
+    // діли
+    case(&["7", "2"], "діли", Stack("3_зціл")),
+    case(&["7.0", "2.0"], "діли", Stack("3.5_дроб")),
+    case(&["10", "2"], "діли", Stack("5_зціл")),
+    case(&["7", "0"], "діли", Panic("Ділення На Нуль")),
+    case(&["7.5", "0_ціл"], "діли", Panic("Ділення На Нуль")),
+    case(&["-9223372036854775808", "-1"], "діли", VALUE_OVERFLOW),
+    case(&["1.0", "0.0"], "діли", VALUE_OVERFLOW),
+    case(&["1"], "діли", UNDERFLOW),
+
+    // остача
+    case(&["10", "3"], "остача", Stack("1_зціл")),
+    case(&["7", "3"], "остача", Stack("1_зціл")),
+    case(&["-7", "3"], "остача", Stack("-1_зціл")),
+    case(&["7_ціл", "3_ціл"], "остача", Stack("1_ціл")),
+    case(&["7.5", "2.0"], "остача", Stack("1.5_дроб")),
+    case(&["-7", "2.5"], "остача", Stack("-2.0_дроб")),
+    case(&["-7", "3_ціл"], "остача", Stack("1_ціл")),
+    case(&["7", "0"], "остача", Panic("Ділення На Нуль")),
+    case(&["7.5", "0.0"], "остача", VALUE_OVERFLOW),
+    case(&["1"], "остача", UNDERFLOW),
+
+    // і
+    case(&["65535", "255"], "і", Stack("255_зціл")),
+    case(&["255", "240_ціл"], "і", Stack("240_ціл")),
+    case(&["12", "10"], "і", Stack("8_зціл")),
+    case(&["12.5", "10"], "і", Panic("Неприпустиме Значення Операнда: 12.5_дроб")),
+    case(&["12", "10.0"], "і", Panic("Неприпустиме Значення Операнда: 10.0_дроб")),
+    case(&["1"], "і", UNDERFLOW),
+
+    // або
+    case(&["4_ціл", "1_ціл"], "або", Stack("5_ціл")),
+    case(&["5", "1"], "або", Stack("5_зціл")),
+    case(&["-8", "3"], "або", Stack("-5_зціл")),
+    case(&["1", "2.0"], "або", Panic("Неприпустиме Значення Операнда: 2.0_дроб")),
+    case(&["2.0", "1_ціл"], "або", Panic("Неприпустиме Значення Операнда: 2.0_дроб")),
+    case(&["1"], "або", UNDERFLOW),
+
+    // абоне
+    case(&["12", "10"], "абоне", Stack("6_зціл")),
+    case(&["7_ціл", "7_ціл"], "абоне", Stack("0_ціл")),
+    case(&["-1", "0"], "абоне", Stack("-1_зціл")),
+    case(&["1", "1.5"], "абоне", Panic("Неприпустиме Значення Операнда: 1.5_дроб")),
+    case(&["1.5", "1"], "абоне", Panic("Неприпустиме Значення Операнда: 1.5_дроб")),
+    case(&["1"], "абоне", UNDERFLOW),
+
+    // корінь
+    case(&["2.25"], "корінь", Stack("1.5_дроб")),
+    case(&["16"], "корінь", Stack("4.0_дроб")),
+    case(&["2_ціл"], "корінь", Stack("1.4142135623730951_дроб")),
+    case(&["0"], "корінь", Stack("0.0_дроб")),
+    case(&["-0.0"], "корінь", Stack("-0.0_дроб")),
+    case(&["-4"], "корінь", Panic("Значення Поза Областю Визначення: -4_зціл")),
+    case(&["-0.25"], "корінь", Panic("Значення Поза Областю Визначення: -0.25_дроб")),
+    case(&[], "корінь", UNDERFLOW),
+
+    // степінь
+    case(&["2", "10"], "степінь", Stack("1024_зціл")),
+    case(&["-8", "3_ціл"], "степінь", Stack("-512_зціл")),
+    case(&["3_ціл", "4.0"], "степінь", Stack("81_ціл")),
+    case(&["0", "0"], "степінь", Stack("1_зціл")),
+    case(&["0_ціл", "0_ціл"], "степінь", Stack("1_ціл")),
+    case(&["0.0", "0.0"], "степінь", Stack("1.0_дроб")),
+    case(&["2.0", "0.5"], "степінь", Stack("1.4142135623730951_дроб")),
+    case(&["2.0", "-1"], "степінь", Stack("0.5_дроб")),
+    case(&["2.5", "2_ціл"], "степінь", Stack("6.25_дроб")),
+    case(&["2", "-1"], "степінь", Panic("Неприпустиме Значення Операнда: -1_зціл")),
+    case(&["2", "0.5"], "степінь", Panic("Неприпустиме Значення Операнда: 0.5_дроб")),
+    case(&["-8.0", "0.5"], "степінь", VALUE_OVERFLOW),
+    case(&["10", "400"], "степінь", VALUE_OVERFLOW),
+    case(&["2", "4294967296"], "степінь", VALUE_OVERFLOW),
+    case(&["10.0", "400"], "степінь", VALUE_OVERFLOW),
+    case(&["1"], "степінь", UNDERFLOW),
+
+    // до_дроб
+    case(&["3"], "до_дроб", Stack("3.0_дроб")),
+    case(&["-3"], "до_дроб", Stack("-3.0_дроб")),
+    case(&["3_ціл"], "до_дроб", Stack("3.0_дроб")),
+    case(&["2.5"], "до_дроб", Stack("2.5_дроб")),
+    case(&[], "до_дроб", UNDERFLOW),
+
+    // до_ціл
+    case(&["3"], "до_ціл", Stack("3_ціл")),
+    case(&["2.7"], "до_ціл", Stack("2_ціл")),
+    case(&["3_ціл"], "до_ціл", Stack("3_ціл")),
+    case(&["-3"], "до_ціл", Panic("Від'ємне Значення Для Беззнакового Типу")),
+    case(&["-0.5"], "до_ціл", Panic("Від'ємне Значення Для Беззнакового Типу")),
+    case(&["1", "2", "1.0"], "до_ціл\nкопію", Stack("1_зціл 2_зціл 1_зціл")),
+    case(&[], "до_ціл", UNDERFLOW),
+
+    // до_зціл
+    case(&["3_ціл"], "до_зціл", Stack("3_зціл")),
+    case(&["-2.7"], "до_зціл", Stack("-2_зціл")),
+    case(&["-3"], "до_зціл", Stack("-3_зціл")),
+    case(&[], "до_зціл", UNDERFLOW),
+
+    // ні
+    case(&["65280"], "ні", Stack("-65281_зціл")),
+    case(&["65280"], "ні\nклади 65535\nі", Stack("255_зціл")),
+    case(&["0_ціл"], "ні", Stack("18446744073709551615_ціл")),
+    case(&["-1"], "ні", Stack("0_зціл")),
+    case(&["1.0"], "ні", Panic("Неприпустиме Значення Операнда: 1.0_дроб")),
+    case(&[], "ні", UNDERFLOW),
+
+    // кількість_бітів
+    case(&["0"], "кількість_бітів", Stack("0_ціл")),
+    case(&["1024_ціл"], "кількість_бітів", Stack("1_ціл")),
+    case(&["255"], "кількість_бітів", Stack("8_ціл")),
+    case(&["18446744073709551615_ціл"], "кількість_бітів", Stack("64_ціл")),
+    case(&["-1"], "кількість_бітів", Stack("64_ціл")),
+    case(&["1.0"], "кількість_бітів", Panic("Неприпустиме Значення Операнда: 1.0_дроб")),
+    case(&[], "кількість_бітів", UNDERFLOW),
+
+    // провідні_нулі
+    case(&["0"], "провідні_нулі", Stack("64_ціл")),
+    case(&["1"], "провідні_нулі", Stack("63_ціл")),
+    case(&["1024_ціл"], "провідні_нулі", Stack("53_ціл")),
+    case(&["18446744073709551615_ціл"], "провідні_нулі", Stack("0_ціл")),
+    case(&["-1"], "провідні_нулі", Stack("0_ціл")),
+    case(&["1.0"], "провідні_нулі", Panic("Неприпустиме Значення Операнда: 1.0_дроб")),
+    case(&[], "провідні_нулі", UNDERFLOW),
+
+    // хвостові_нулі
+    case(&["0"], "хвостові_нулі", Stack("64_ціл")),
+    case(&["1"], "хвостові_нулі", Stack("0_ціл")),
+    case(&["1024_ціл"], "хвостові_нулі", Stack("10_ціл")),
+    case(&["18446744073709551615_ціл"], "хвостові_нулі", Stack("0_ціл")),
+    case(&["-9223372036854775808"], "хвостові_нулі", Stack("63_ціл")),
+    case(&["1.0"], "хвостові_нулі", Panic("Неприпустиме Значення Операнда: 1.0_дроб")),
+    case(&[], "хвостові_нулі", UNDERFLOW),
+
+    // вліво
+    case(&["1", "4"], "вліво", Stack("16_зціл")),
+    case(&["3_ціл", "2_ціл"], "вліво", Stack("12_ціл")),
+    case(&["1", "65"], "вліво", Stack("2_зціл")),
+    case(&["-1", "63"], "вліво", Stack("-9223372036854775808_зціл")),
+    case(&["1.0", "1"], "вліво", Panic("Неприпустиме Значення Операнда: 1.0_дроб")),
+    case(&["1_ціл", "2.7"], "вліво", Panic("Неприпустиме Значення Операнда: 2.7_дроб")),
+    case(&["1", "-1"], "вліво", Panic("Неприпустиме Значення Операнда: -1_зціл")),
+    case(&["1", "4294967297_ціл"], "вліво", Stack("2_зціл")),
+    case(&["1"], "вліво", UNDERFLOW),
+
+    // вправо
+    case(&["16", "4"], "вправо", Stack("1_зціл")),
+    case(&["-16", "2"], "вправо", Stack("-4_зціл")),
+    case(&["-1", "63"], "вправо", Stack("-1_зціл")),
+    case(&["18446744073709551615_ціл", "63"], "вправо", Stack("1_ціл")),
+    case(&["8_ціл", "66"], "вправо", Stack("2_ціл")),
+    case(&["2.5", "1"], "вправо", Panic("Неприпустиме Значення Операнда: 2.5_дроб")),
+    case(&["16_ціл", "1.0"], "вправо", Panic("Неприпустиме Значення Операнда: 1.0_дроб")),
+    case(&["16", "-2"], "вправо", Panic("Неприпустиме Значення Операнда: -2_зціл")),
+    case(&["1"], "вправо", UNDERFLOW),
+
+    // поворот_л
+    case(&["1", "1"], "поворот_л", Stack("2_зціл")),
+    case(&["-9223372036854775808", "1"], "поворот_л", Stack("1_зціл")),
+    case(&["9223372036854775808_ціл", "4"], "поворот_л", Stack("8_ціл")),
+    case(&["3_ціл", "64"], "поворот_л", Stack("3_ціл")),
+    case(&["3_ціл", "65"], "поворот_л", Stack("6_ціл")),
+    case(&["-6", "1"], "поворот_л\nклади 1\nповорот_п", Stack("-6_зціл")),
+    case(&["12297829382473034410_ціл", "7"], "поворот_л\nклади 7\nповорот_п", Stack("12297829382473034410_ціл")),
+    case(&["2.5", "1"], "поворот_л", Panic("Неприпустиме Значення Операнда: 2.5_дроб")),
+    case(&["1_ціл", "-1"], "поворот_л", Panic("Неприпустиме Значення Операнда: -1_зціл")),
+    case(&["1_ціл", "1.5"], "поворот_л", Panic("Неприпустиме Значення Операнда: 1.5_дроб")),
+    case(&["1"], "поворот_л", UNDERFLOW),
+
+    // поворот_п
+    case(&["2", "1"], "поворот_п", Stack("1_зціл")),
+    case(&["1", "1"], "поворот_п", Stack("-9223372036854775808_зціл")),
+    case(&["1_ціл", "4"], "поворот_п", Stack("1152921504606846976_ціл")),
+    case(&["6_ціл", "65"], "поворот_п", Stack("3_ціл")),
+    case(&["0_ціл", "5"], "поворот_п\nклади 5\nповорот_л", Stack("0_ціл")),
+    case(&["18446744073709551615_ціл", "1"], "поворот_п\nклади 1\nповорот_л", Stack("18446744073709551615_ціл")),
+    case(&["2.5", "1"], "поворот_п", Panic("Неприпустиме Значення Операнда: 2.5_дроб")),
+    case(&["2_ціл", "-1"], "поворот_п", Panic("Неприпустиме Значення Операнда: -1_зціл")),
+    case(&["2_ціл", "1.0"], "поворот_п", Panic("Неприпустиме Значення Операнда: 1.0_дроб")),
+    case(&["1"], "поворот_п", UNDERFLOW),
+
+    // не
+    case(&["0"], "не", Stack("1_ціл")),
+    case(&["5_ціл"], "не", Stack("0_ціл")),
+    case(&["-3"], "не", Stack("0_ціл")),
+    case(&["0.0"], "не", Stack("1_ціл")),
+    case(&["-0.0"], "не", Stack("1_ціл")),
+    case(&["0.5"], "не", Stack("0_ціл")),
+    case(&["1", "2"], "рівн\nне\nкрок? кінець_специфікації\nклади 9", Stack("1_зціл 2_зціл")),
+    case(&["2", "2"], "рівн\nне\nкрок? кінець_специфікації\nклади 9", Stack("2_зціл 2_зціл 9_зціл")),
+    case(&[], "не", UNDERFLOW),
+
+    // менше
+    case(&["1", "2"], "менше", Stack("1_ціл")),
+    case(&["2", "1"], "менше", Stack("0_ціл")),
+    case(&["2", "2"], "менше", Stack("0_ціл")),
+    case(&["-3", "2"], "менше", Stack("1_ціл")),
+    case(&["1_ціл", "2_ціл"], "менше", Stack("1_ціл")),
+    case(&["2_ціл", "1_ціл"], "менше", Stack("0_ціл")),
+    case(&["1.5", "2.5"], "менше", Stack("1_ціл")),
+    case(&["2.5", "1.5"], "менше", Stack("0_ціл")),
+    case(&["2.5", "3"], "менше", Stack("1_ціл")),
+    case(&["-3", "2_ціл"], "менше", Stack("0_ціл")),
+    case(&["NaN", "1.0"], "менше", Stack("0_ціл")),
+    case(&["1.0", "NaN"], "менше", Stack("0_ціл")),
+    case(&["1"], "менше", UNDERFLOW),
+
+    // більше
+    case(&["2", "1"], "більше", Stack("1_ціл")),
+    case(&["1", "2"], "більше", Stack("0_ціл")),
+    case(&["2", "2"], "більше", Stack("0_ціл")),
+    case(&["2", "-3"], "більше", Stack("1_ціл")),
+    case(&["2_ціл", "1_ціл"], "більше", Stack("1_ціл")),
+    case(&["1_ціл", "2_ціл"], "більше", Stack("0_ціл")),
+    case(&["2.5", "1.5"], "більше", Stack("1_ціл")),
+    case(&["1.5", "2.5"], "більше", Stack("0_ціл")),
+    case(&["2.5", "2"], "більше", Stack("0_ціл")),
+    case(&["NaN", "1.0"], "більше", Stack("0_ціл")),
+    case(&["1.0", "NaN"], "більше", Stack("0_ціл")),
+    case(&["1"], "більше", UNDERFLOW),
+
+    // мменше
+    case(&["1", "2"], "мменше", Stack("1_ціл")),
+    case(&["2", "2"], "мменше", Stack("1_ціл")),
+    case(&["3", "2"], "мменше", Stack("0_ціл")),
+    case(&["2_ціл", "2_ціл"], "мменше", Stack("1_ціл")),
+    case(&["3_ціл", "2_ціл"], "мменше", Stack("0_ціл")),
+    case(&["2.5", "2.5"], "мменше", Stack("1_ціл")),
+    case(&["2.5", "1.5"], "мменше", Stack("0_ціл")),
+    case(&["NaN", "NaN"], "мменше", Stack("0_ціл")),
+    case(&["1.0", "NaN"], "мменше", Stack("0_ціл")),
+    case(&["1"], "мменше", UNDERFLOW),
+
+    // мбільше
+    case(&["2", "1"], "мбільше", Stack("1_ціл")),
+    case(&["2", "2"], "мбільше", Stack("1_ціл")),
+    case(&["2", "3"], "мбільше", Stack("0_ціл")),
+    case(&["2_ціл", "2_ціл"], "мбільше", Stack("1_ціл")),
+    case(&["2_ціл", "3_ціл"], "мбільше", Stack("0_ціл")),
+    case(&["2.5", "2.5"], "мбільше", Stack("1_ціл")),
+    case(&["1.5", "2.5"], "мбільше", Stack("0_ціл")),
+    case(&["NaN", "NaN"], "мбільше", Stack("0_ціл")),
+    case(&["NaN", "1.0"], "мбільше", Stack("0_ціл")),
+    case(&["1"], "мбільше", UNDERFLOW),
+
+    // пор
+    case(&["1", "2"], "пор", Stack("-1_зціл")),
+    case(&["2", "2"], "пор", Stack("0_зціл")),
+    case(&["3", "2"], "пор", Stack("1_зціл")),
+    case(&["1_ціл", "2_ціл"], "пор", Stack("-1_зціл")),
+    case(&["2_ціл", "2_ціл"], "пор", Stack("0_зціл")),
+    case(&["3_ціл", "2_ціл"], "пор", Stack("1_зціл")),
+    case(&["1.5", "2.5"], "пор", Stack("-1_зціл")),
+    case(&["2.5", "2.5"], "пор", Stack("0_зціл")),
+    case(&["3.5", "2.5"], "пор", Stack("1_зціл")),
+    case(&["-1", "0.5"], "пор", Stack("-1_зціл")),
+    case(&["3", "2.5"], "пор", Stack("1_зціл")),
+    case(&["2", "2"], "пор\nкрок_нуль кінець_специфікації\nклади 9", Stack("")),
+    case(&["NaN", "1.0"], "пор", Panic("Неприпустиме Значення Операнда: NaN_дроб")),
+    case(&["1.0", "NaN"], "пор", Panic("Неприпустиме Значення Операнда: NaN_дроб")),
+    case(&["1"], "пор", UNDERFLOW),
+
+    // відємн
+    case(&["5"], "відємн", Stack("-5_зціл")),
+    case(&["-5"], "відємн", Stack("5_зціл")),
+    case(&["5"], "відємн\nвідємн", Stack("5_зціл")),
+    case(&["2.5"], "відємн", Stack("-2.5_дроб")),
+    case(&["2.5"], "відємн\nвідємн", Stack("2.5_дроб")),
+    case(&["0.0"], "відємн", Stack("-0.0_дроб")),
+    case(&["1_ціл"], "відємн", Stack("18446744073709551615_ціл")),
+    case(&["7_ціл"], "відємн\nвідємн", Stack("7_ціл")),
+    case(&["-9223372036854775808"], "відємн", VALUE_OVERFLOW),
+    case(&[], "відємн", UNDERFLOW),
+
+    // абсол
+    case(&["-5"], "абсол", Stack("5_зціл")),
+    case(&["5"], "абсол", Stack("5_зціл")),
+    case(&["-2.5"], "абсол", Stack("2.5_дроб")),
+    case(&["-0.0"], "абсол", Stack("0.0_дроб")),
+    case(&["-inf"], "абсол", Stack("inf_дроб")),
+    case(&["7_ціл"], "абсол", Stack("7_ціл")),
+    case(&["-9223372036854775808"], "абсол", VALUE_OVERFLOW),
+    case(&[], "абсол", UNDERFLOW),
+
+    // підлога
+    case(&["0.5"], "підлога", Stack("0.0_дроб")),
+    case(&["-0.5"], "підлога", Stack("-1.0_дроб")),
+    case(&["2.0"], "підлога", Stack("2.0_дроб")),
+    case(&["-7"], "підлога", Stack("-7_зціл")),
+    case(&["7_ціл"], "підлога", Stack("7_ціл")),
+    case(&["-2.5"], "підлога", Stack("-3.0_дроб")),
+    case(&["-3.0"], "підлога", Stack("-3.0_дроб")),
+    case(&[], "підлога", UNDERFLOW),
+
+    // стеля
+    case(&["0.5"], "стеля", Stack("1.0_дроб")),
+    case(&["-0.5"], "стеля", Stack("-0.0_дроб")),
+    case(&["2.0"], "стеля", Stack("2.0_дроб")),
+    case(&["-7"], "стеля", Stack("-7_зціл")),
+    case(&["-2.5"], "стеля", Stack("-2.0_дроб")),
+    case(&["-3.0"], "стеля", Stack("-3.0_дроб")),
+    case(&[], "стеля", UNDERFLOW),
+
+    // округл
+    case(&["0.5"], "округл", Stack("1.0_дроб")),
+    case(&["-0.5"], "округл", Stack("-1.0_дроб")),
+    case(&["0.49"], "округл", Stack("0.0_дроб")),
+    case(&["2.0"], "округл", Stack("2.0_дроб")),
+    case(&["7_ціл"], "округл", Stack("7_ціл")),
+    case(&["-2.5"], "округл", Stack("-3.0_дроб")),
+    case(&["-3.0"], "округл", Stack("-3.0_дроб")),
+    case(&[], "округл", UNDERFLOW),
+
+    // ззовні
+    case(&["5"], "ззовні 0", Stack("5_зціл")),
+    case(&["5"], "ззовні 7", Panic("Неприпустиме Значення Операнда: 7_зціл")),
+    case(&[], "ззовні 0", UNDERFLOW),
+
+    // друк
+    case(&["1", "5"], "друк", Stack("1_зціл")),
+    case(&[], "друк", UNDERFLOW),
+
+    // друкн
+    case(&["1", "5"], "друкн\nклади 10_ціл\nдрукз", Stack("1_зціл")),
+    case(&[], "друкн", UNDERFLOW),
+
+    // друкз
+    case(&["1", "10_ціл"], "друкз", Stack("1_зціл")),
+    case(&["65"], "друкз", Panic("Неприпустиме Значення Операнда: 65_зціл")),
+    case(&["55296_ціл"], "друкз", Panic("Неприпустиме Значення Операнда: 55296_ціл")),
+    case(&["1114112_ціл"], "друкз", Panic("Неприпустиме Значення Операнда: 1114112_ціл")),
+    case(&[], "друкз", UNDERFLOW),
+
+    // перевір
+    case(&["1", "1"], "перевір", Stack("1_зціл")),
+    case(&["-1"], "перевір", Stack("")),
+    case(&["0.5"], "перевір", Stack("")),
+    case(&["0"], "перевір", Panic("Перевірку Не Пройдено: за адресою 1")),
+    case(&["0_ціл"], "перевір", Panic("Перевірку Не Пройдено: за адресою 1")),
+    case(&["2", "2"], "рівн\nперевір", Stack("2_зціл 2_зціл")),
+    case(&[], "перевір", UNDERFLOW),
+
+    // сис
+    case(&["1", "0", "0_ціл"], "сис 1", Stack("0_ціл")),
+    case(&["3", "0", "0_ціл"], "сис 1", Panic("Неприпустиме Значення Операнда: 3_зціл")),
+    case(&["256", "0"], "збережи\nклади 2\nклади 0\nклади 1_ціл\nсис 1", Panic("Неприпустиме Значення Операнда: 256_зціл")),
+    case(&["1", "65535", "2_ціл"], "сис 1", Panic("Адреса Поза Пам'яттю: 65535_зціл")),
+    case(&["1", "1", "18446744073709551615_ціл"], "сис 1", Panic("Адреса Поза Пам'яттю: 1_зціл")),
+    case(&["0", "0_ціл"], "сис 1", UNDERFLOW),
+    case(&[], "сис 0", Panic("Невідомий Системний Виклик: 0")),
+    case(&[], "сис 4", Panic("Невідомий Системний Виклик: 4")),
+    // The spec runs without --allow-fs
+    case(&[], "дані ф: \"x\"\nклади ф\nклади 1\nклади 10\nсис файл_читати", Panic("Доступ Заборонено: x")),
+    case(&[], "дані ф: \"x\"\nклади ф\nклади 1\nклади 10\nклади 0_ціл\nсис файл_писати", Panic("Доступ Заборонено: x")),
+    case(&["1"], "сис файл_читати", UNDERFLOW),
+
+    // друкр
+    case(&["1", "0", "0_ціл"], "друкр", Stack("1_зціл")),
+    case(&["10_ціл", "0"], "збережи\nклади 0\nклади 1_ціл\nдрукр", Stack("")),
+    case(&["0", "1_ціл"], "друкр", Panic("Неприпустиме Значення Операнда: 0_зціл")),
+    case(&["65535", "2_ціл"], "друкр", Panic("Адреса Поза Пам'яттю: 65535_зціл")),
+    case(&["-1", "0_ціл"], "друкр", Panic("Адреса Поза Пам'яттю: -1_зціл")),
+    // The end of the string would wrap around the address space
+    case(&["1", "18446744073709551615_ціл"], "друкр", Panic("Адреса Поза Пам'яттю: 1_зціл")),
+    case(&["0"], "друкр", UNDERFLOW),
+
+    // вертай
+    case(&[], "клич ф\nкрок кінець_специфікації\nф: вертай", Stack("")),
+    case(&[], "вертай", UNDERFLOW),
+
+    // клич
+    case(&[], "клич ф\nф: неоп", Stack("1_ціл")),
+    case(&["0"], "клич? ф\nф: неоп", Stack("")),
+
+    // кінчай
+    case(&["1"], "кінчай\nклади 2", Halt),
+    case(&["1", "0"], "кінчай?", Stack("1_зціл")),
+
+    // міняй
+    case(&["1", "2", "3"], "міняй 2", Stack("3_зціл 2_зціл 1_зціл")),
+    case(&["1", "2"], "міняй 1", Stack("2_зціл 1_зціл")),
+    case(&["1", "2"], "міняй 2", UNDERFLOW),
+    case(&["1"], "міняй 1", UNDERFLOW),
+
+    // обмін
+    case(&["1", "2", "3"], "обмін", Stack("1_зціл 3_зціл 2_зціл")),
+    case(&["7_ціл", "2.5"], "обмін", Stack("2.5_дроб 7_ціл")),
+    case(&["1", "2.5_дроб", "0"], "обмін?", Stack("1_зціл 2.5_дроб")),
+    case(&["1", "2.5_дроб", "1"], "обмін?", Stack("2.5_дроб 1_зціл")),
+    case(&["1"], "обмін", UNDERFLOW),
+
+    // понад
+    case(&["1", "2"], "понад", Stack("1_зціл 2_зціл 1_зціл")),
+    case(&["1.5", "2_ціл", "0"], "понад?", Stack("1.5_дроб 2_ціл")),
+    case(&["1"], "понад", UNDERFLOW),
+    case(&[FULL], "понад", OVERFLOW),
+
+    // оберт
+    case(&["1", "2", "3"], "оберт", Stack("2_зціл 3_зціл 1_зціл")),
+    case(&["0.5", "1", "2", "3", "0"], "оберт?", Stack("0.5_дроб 1_зціл 2_зціл 3_зціл")),
+    case(&["2", "3"], "оберт", UNDERFLOW),
+
+    // оберт_назад
+    case(&["0.5", "1", "2", "3"], "оберт_назад", Stack("0.5_дроб 3_зціл 1_зціл 2_зціл")),
+    case(&["1", "2", "3"], "оберт\nоберт_назад", Stack("1_зціл 2_зціл 3_зціл")),
+    case(&["2", "3"], "оберт_назад", UNDERFLOW),
+
+    // ролл
+    case(&["1", "2", "3"], "ролл 0", Stack("1_зціл 2_зціл 3_зціл")),
+    case(&["1", "2", "3"], "ролл 1", Stack("1_зціл 3_зціл 2_зціл")),
+    case(&["1", "2", "3"], "ролл 2", Stack("2_зціл 3_зціл 1_зціл")),
+    case(&["1", "2.5", "3_ціл", "4"], "ролл 2", Stack("1_зціл 3_ціл 4_зціл 2.5_дроб")),
+    case(&["1", "2", "3"], "ролл 3", Panic("Неприпустиме Значення Операнда: 3_зціл")),
+    case(&[], "ролл 0", Panic("Неприпустиме Значення Операнда: 0_зціл")),
+
+    // межа
+    case(&["2"], "межа 3", Stack("2_зціл")),
+    case(&["3"], "межа 3", Panic("Неприпустиме Значення Операнда: 3_зціл")),
+    case(&["-1"], "межа 3", Panic("Неприпустиме Значення Операнда: -1_зціл")),
+    case(&["1.5"], "межа 3", Panic("Неприпустиме Значення Операнда: 1.5_дроб")),
+    case(&[], "межа 3", UNDERFLOW),
+
+    // таблиця (expands into межа and a jump through вертай)
+    case(&["0"], "таблиця а б в\nа: клади 10\nкрок кінець_специфікації\nб: клади 20\nкрок кінець_специфікації\nв: клади 30", Stack("10_зціл")),
+    case(&["2"], "таблиця а б в\nа: клади 10\nкрок кінець_специфікації\nб: клади 20\nкрок кінець_специфікації\nв: клади 30", Stack("30_зціл")),
+    case(&["3"], "таблиця а б в\nа: клади 10\nкрок кінець_специфікації\nб: клади 20\nкрок кінець_специфікації\nв: клади 30", Panic("Неприпустиме Значення Операнда: 3_зціл")),
+    case(&["-1"], "таблиця а б в\nа: клади 10\nкрок кінець_специфікації\nб: клади 20\nкрок кінець_специфікації\nв: клади 30", Panic("Неприпустиме Значення Операнда: -1_зціл")),
+    case(&[], "таблиця а б в\nа: клади 10\nкрок кінець_специфікації\nб: клади 20\nкрок кінець_специфікації\nв: клади 30", UNDERFLOW),
+    // The table base is counted from the code before it
+    case(&["7", "1"], "неоп\nклади 5\nкинь\nтаблиця а б в\nа: клади 10\nкрок кінець_специфікації\nб: клади 20\nкрок кінець_специфікації\nв: клади 30", Stack("7_зціл 20_зціл")),
+
+    // читай (the input of the spec runs is empty)
+    case(&[], "читай", Panic("Кінець Вводу")),
+    case(&[], "дані п: \"? \"\nклади п\nклади 2\nчитай 1", Panic("Кінець Вводу")),
+    case(&[], "читай 2", Panic("Неприпустиме Значення Операнда: 2_зціл")),
+    case(&["0", "65537"], "читай 1", Panic("Адреса Поза Пам'яттю: 0_зціл")),
+
+    // читай_спроба
+    case(&[], "читай_спроба", Stack("0_ціл")),
+    case(&[], "дані п: \"? \"\nклади п\nклади 2\nчитай_спроба 1", Stack("0_ціл")),
+    case(&["1"], "читай_спроба 1", UNDERFLOW),
+    case(&[FULL], "читай_спроба", OVERFLOW),
+
+    // читайз
+    case(&[], "читайз", Stack("-1_зціл")),
+    case(&[FULL], "читайз", OVERFLOW),
+
+    // випадк (the seed is different on every run)
+    case(&["1"], "випадк\nкинь", Stack("1_зціл")),
+    case(&[FULL], "випадк", OVERFLOW),
+
+    // тік
+    case(&[], "тік\nтік\nмменше", Stack("1_ціл")),
+    case(&[FULL], "тік", OVERFLOW),
+
+    // збережи
+    case(&["1", "7", "3"], "збережи", Stack("1_зціл")),
+    case(&["7", "65535"], "збережи", Stack("")),
+    case(&["7", "65536"], "збережи", Panic("Адреса Поза Пам'яттю: 65536_зціл")),
+    case(&["7", "-1"], "збережи", Panic("Адреса Поза Пам'яттю: -1_зціл")),
+    case(&["7", "1.5"], "збережи", Panic("Адреса Поза Пам'яттю: 1.5_дроб")),
+    case(&["3"], "збережи", UNDERFLOW),
+
+    // завантаж
+    case(&["2.5", "3"], "збережи\nклади 3_ціл\nзавантаж", Stack("2.5_дроб")),
+    case(&["9"], "завантаж", Stack("0_зціл")),
+    case(&["65536"], "завантаж", Panic("Адреса Поза Пам'яттю: 65536_зціл")),
+    case(&[], "завантаж", UNDERFLOW),
+
+    // у_рег
+    case(&["1", "5"], "у_рег 0", Stack("1_зціл")),
+    case(&["5"], "у_рег 7\nз_рег 7\nз_рег 7", Stack("5_зціл 5_зціл")),
+    case(&["5"], "у_рег 8", Panic("Неприпустиме Значення Операнда: 8_зціл")),
+    case(&[], "у_рег 0", UNDERFLOW),
+
+    // з_рег
+    case(&[], "з_рег 2", Stack("0_ціл")),
+    case(&["2.5", "1"], "у_рег 1\nу_рег 1\nз_рег 1", Stack("2.5_дроб")),
+    case(&[], "з_рег 8", Panic("Неприпустиме Значення Операнда: 8_зціл")),
+    case(&[FULL], "з_рег 0", OVERFLOW),
+
+    // тип
+    case(&["-1"], "тип", Stack("-1_зціл 1_ціл")),
+    case(&["1_ціл"], "тип", Stack("1_ціл 2_ціл")),
+    case(&["1.0"], "тип", Stack("1.0_дроб 3_ціл")),
+    case(&["1.0"], "тип\nклади 3_ціл\nрівн\nкрок? кінець_специфікації\nклади 9", Stack("1.0_дроб 3_ціл 3_ціл")),
+    case(&["-1"], "тип\nклади ТИП_ЗЦІЛ\nрівн", Stack("-1_зціл 1_ціл 1_ціл 1_ціл")),
+    case(&["1_ціл"], "тип\nклади ТИП_ЦІЛ\nрівн", Stack("1_ціл 2_ціл 2_ціл 1_ціл")),
+    case(&["1.0"], "тип\nклади ТИП_ДРОБ\nрівн", Stack("1.0_дроб 3_ціл 3_ціл 1_ціл")),
+    case(&[], "тип", UNDERFLOW),
+    case(&[FULL], "тип", OVERFLOW),
+
+    // привести
+    case(&["-3"], "привести 0", Stack("-3_зціл")),
+    case(&["3"], "привести 1", Stack("3_ціл")),
+    case(&["-3"], "привести 1", Panic("Від'ємне Значення Для Беззнакового Типу")),
+    case(&["-3"], "привести 2", Stack("-3.0_дроб")),
+    case(&["3_ціл"], "привести 0", Stack("3_зціл")),
+    case(&["3_ціл"], "привести 1", Stack("3_ціл")),
+    case(&["3_ціл"], "привести 2", Stack("3.0_дроб")),
+    case(&["-2.7"], "привести 0", Stack("-2_зціл")),
+    case(&["2.7"], "привести 1", Stack("2_ціл")),
+    case(&["-2.7"], "привести 1", Panic("Від'ємне Значення Для Беззнакового Типу")),
+    case(&["2.7"], "привести 2", Stack("2.7_дроб")),
+    case(&["1_ціл", "2.5"], "тип\nпривести 0", Stack("1_ціл 2.5_дроб 3_зціл")),
+    case(&["7"], "привести 3", Panic("Неприпустиме Значення Операнда: 3_зціл")),
+    case(&["7"], "привести -1", Panic("Неприпустиме Значення Операнда: -1_зціл")),
+    case(&[], "привести 0", UNDERFLOW),
+
+    // глибина
+    case(&[], "глибина", Stack("0_ціл")),
+    case(&["1", "2.5", "3_ціл"], "глибина", Stack("1_зціл 2.5_дроб 3_ціл 3_ціл")),
+    case(&["7"], "глибина\nглибина", Stack("7_зціл 1_ціл 2_ціл")),
+    case(&[FULL], "глибина", OVERFLOW),
+
+    // статистика
+    case(&[], "статистика 0", Stack("0_ціл")),
+    case(&["1", "2"], "статистика 0", Stack("1_зціл 2_зціл 2_ціл")),
+    case(&["1", "2"], "кинь\nстатистика 1", Stack("1_зціл 2_ціл")),
+    case(&[], "неоп\nнеоп\nстатистика 0\nнеоп\nстатистика 0", Stack("2_ціл 4_ціл")),
+    case(&[], "клади 1\nклади 2\nклади 3\nкинь\nкинь\nстатистика 1", Stack("1_зціл 3_ціл")),
+    // The pushed mark counts towards the next one
+    case(&[], "статистика 1\nстатистика 1", Stack("0_ціл 1_ціл")),
+    case(&[], "статистика 2", Stack("18446744073709551615_ціл")),
+    case(&[], "статистика 3", Panic("Неприпустиме Значення Операнда: 3_зціл")),
+    case(&[FULL], "статистика 0", OVERFLOW),
+
+    // кадр
+    case(&[], "кадр 2\nлокал 1", Stack("0_зціл")),
+    case(&[], "кадр 2000", OVERFLOW),
+
+    // локал
+    case(&[], "локал 0", NO_FRAME),
+    case(&[], "кадр 1\nлокал 1", Panic("Неприпустиме Значення Операнда: 1_зціл")),
+
+    // локал_зберіг
+    case(&["5"], "кадр 1\nлокал_зберіг 0\nлокал 0", Stack("5_зціл")),
+    case(&["5"], "кадр 1\nкадр 1\nлокал_зберіг 0\nназад\nлокал 0", Stack("0_зціл")),
+    case(&[], "кадр 1\nлокал_зберіг 0", UNDERFLOW),
+    case(&["5"], "локал_зберіг 0", NO_FRAME),
+
+    // назад
+    case(&[], "кадр 1\nназад", Stack("")),
+    case(&[], "назад", NO_FRAME),
+
+    // виклик
+    case(&["1"], "виклик ф\nкрок кінець_специфікації\nф: клади 2\nповернись", Stack("1_зціл 2_зціл")),
+    case(&["0"], "виклик? ф\nкрок кінець_специфікації\nф: клади 2\nповернись", Stack("")),
+
+    // повернись
+    case(&[], "повернись", Panic("Незаповненість Стека Повернень")),
+    case(&["0"], "повернись?", Stack("")),
+];
+
+fn full_stack() -> String {
+    // The loop keeps two more values on the stack than it leaves behind
+    "кадр 1
+    клади 1022_ціл
+    локал_зберіг 0
+    заповнити:
+        клади 0
+        локал 0
+        клади 1_ціл
+        різн
+        копію 0
+        локал_зберіг 0
+        крок? заповнити
+    назад
+    клади 0
+    клади 0
+    "
+    .to_string()
+}
+
+fn source(case: &Case) -> String {
+    let mut src = String::new();
+    for value in case.stack {
+        if *value == FULL {
+            src.push_str(&full_stack());
+        } else {
+            src.push_str(&format!("клади {value}\n"));
+        }
+    }
+    src.push_str(case.src);
+    src.push_str("\nкінець_специфікації: неоп\n");
+
+    src
+}
+
+fn check(case: &Case, path: &std::path::Path) -> Result<(), String> {
+    fs::write(path, source(case)).unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .args(["emu", "-usm", "--snapshot-at", "кінець_специфікації"])
+        .arg(path)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    let snapshot = stdout
+        .lines()
+        .find_map(|l| l.strip_prefix("ЗНІМОК кінець_специфікації "))
+        .and_then(|l| l.split_once(" : ").map(|(_, stack)| stack));
+
+    match (&case.expect, out.status.success()) {
+        (Stack(expected), true) if snapshot == Some(expected) => Ok(()),
+        (Halt, true) if snapshot.is_none() => Ok(()),
+        (Panic(expected), false) if stderr.lines().last() == Some(expected) => Ok(()),
+        _ => Err(format!(
+            "got stack {snapshot:?}, stdout {stdout:?}, stderr {stderr:?}"
+        )),
+    }
+}
+
+#[test]
+fn instructions_follow_the_spec() {
+    let path = env::temp_dir().join(format!("uvm-spec-{}.usm", std::process::id()));
+    let failures = SPEC
+        .iter()
+        .filter_map(|case| {
+            check(case, &path).err().map(|e| {
+                format!(
+                    "{stack:?} \"{src}\": {e}",
+                    stack = case.stack,
+                    src = case.src.replace('\n', "; ")
+                )
+            })
+        })
+        .collect::<Vec<_>>();
+    _ = fs::remove_file(path);
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+#[test]
+fn every_instruction_has_spec_cases() {
+    let out = Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .arg("doc-isa")
+        .output()
+        .unwrap();
+    let reference = String::from_utf8_lossy(&out.stdout);
+    let missing = reference
+        .lines()
+        .skip(1)
+        .filter_map(|l| l.split_whitespace().next())
+        .filter(|mnemonic| {
+            !SPEC.iter().any(|case| {
+                case.src
+                    .lines()
+                    .filter_map(|l| l.split_whitespace().next())
+                    .any(|word| word.trim_end_matches('?') == *mnemonic)
+            })
+        })
+        .collect::<Vec<_>>();
+
+    assert!(missing.is_empty(), "no spec cases for {missing:?}");
+}
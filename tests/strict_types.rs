@@ -0,0 +1,64 @@
+use std::{
+    env, fs,
+    process::{Command, Output},
+};
+
+fn run(name: &str, src: &str, opts: &[&str]) -> Output {
+    let path = env::temp_dir().join(format!("uvm-strict-{name}-{}.usm", std::process::id()));
+    fs::write(&path, src).unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .args(["emu", "-usm"])
+        .args(opts)
+        .arg(&path)
+        .output()
+        .unwrap();
+    _ = fs::remove_file(path);
+
+    out
+}
+
+fn stderr(out: &Output) -> String {
+    String::from_utf8_lossy(&out.stderr).trim_end().to_string()
+}
+
+#[test]
+fn bare_literal_is_rejected_with_a_suggestion() {
+    let out = run("bare", "клади 1_ціл\nклади 2\n", &["--strict-types"]);
+    assert_eq!(out.status.code(), Some(1));
+    assert_eq!(
+        stderr(&out),
+        "Помилка Перекладу: 2:7: значення \"2\" без суфіксу типу, можливо \"2_зціл\""
+    );
+    let out = run("bare-float", "клади 0.5\n", &["--strict-types"]);
+    assert!(
+        stderr(&out).ends_with("можливо \"0.5_дроб\""),
+        "{}",
+        stderr(&out)
+    );
+}
+
+#[test]
+fn suffixed_literals_and_labels_are_accepted() {
+    let src = "клади 1_ціл\nклади -2_зціл\nклади 0.5_дроб\nкрок кінець\nкінець:\nкінчай\n";
+    let out = run("suffixed", src, &["--strict-types"]);
+    assert!(out.status.success(), "{}", stderr(&out));
+}
+
+#[test]
+fn bare_literals_are_allowed_by_default() {
+    assert!(run("default", "клади 1\nклади 2.5\n", &[]).status.success());
+}
+
+#[test]
+fn directive_applies_from_its_line_on() {
+    let src = "клади 1\n!строгі_типи\nклади 2_ціл\nклади 3\n";
+    let out = run("directive", src, &[]);
+    assert_eq!(out.status.code(), Some(1));
+    assert_eq!(
+        stderr(&out),
+        "Помилка Перекладу: 4:7: значення \"3\" без суфіксу типу, можливо \"3_зціл\""
+    );
+    assert!(run("before-directive", "клади 1\n!строгі_типи\n", &[])
+        .status
+        .success());
+}
@@ -0,0 +1,39 @@
+use std::{path::Path, process::Command};
+
+fn summary(program: &str) -> String {
+    let out = Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .args(["emu", "-v"])
+        .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join(program))
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+
+    String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .last()
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn summary_reports_stack_high_water_mark() {
+    // 5 return addresses and the doubled zero at the deepest call
+    assert!(summary("tests/programs/recursion.usm")
+        .contains("Найбільше: стек [7], кадри [0], виклики [0], адреса пам'яті [-]."));
+}
+
+#[test]
+fn summary_reports_frame_depth() {
+    // The inner routine opens its frame while the outer one is still open
+    assert!(summary("tests/programs/frame_locals.usm")
+        .contains("Найбільше: стек [3], кадри [2], виклики [0], адреса пам'яті [-]."));
+}
+
+#[test]
+fn summary_reports_call_depth_and_memory() {
+    assert!(summary("tests/programs/nested_calls.usm")
+        .contains("Найбільше: стек [2], кадри [0], виклики [3], адреса пам'яті [10]."));
+    // The `дані` values are in the memory from the start, the last one is at 4
+    assert!(summary("tests/programs/squares.usm")
+        .contains("Найбільше: стек [3], кадри [0], виклики [0], адреса пам'яті [4]."));
+}
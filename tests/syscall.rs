@@ -0,0 +1,62 @@
+use std::{
+    env, fs,
+    process::{Command, Output},
+};
+
+fn run(name: &str, src: &str, args: &[&str]) -> Output {
+    let program = env::temp_dir().join(format!("uvm_syscall_{}_{name}.usm", std::process::id()));
+    fs::write(&program, src).unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_uvm"))
+        .args(["emu", "-usm"])
+        .args(args)
+        .arg(&program)
+        .output()
+        .unwrap();
+    fs::remove_file(&program).unwrap();
+
+    out
+}
+
+// Writes "ок\n" (4 bytes of UTF-8) to the fd and prints the number of written bytes
+fn write_to(fd: usize) -> String {
+    format!(
+        "дані ок: 208 190 208 186 10
+        клади {fd}
+        клади ок
+        клади 5_ціл
+        сис 1
+        друк"
+    )
+}
+
+#[test]
+fn write_goes_to_stdout() {
+    let out = run("stdout", &write_to(1), &[]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "ок\n5_ціл\n");
+    assert!(out.stderr.is_empty());
+}
+
+#[test]
+fn write_goes_to_stderr() {
+    let out = run("stderr", &write_to(2), &[]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "5_ціл\n");
+    assert_eq!(String::from_utf8_lossy(&out.stderr), "ок\n");
+}
+
+#[test]
+fn syscalls_are_natives() {
+    let out = run("denied", &write_to(1), &["--deny", "natives"]);
+    assert!(!out.status.success());
+    assert!(out.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Заборонена Інструкція: \"сис\""));
+}